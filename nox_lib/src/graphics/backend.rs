@@ -0,0 +1,359 @@
+use std::fmt::Write as _;
+use std::io::{self, Stdout, Write};
+
+use super::{Cell, Color, ColorDepth, Style};
+
+/// Something a gui can be displayed on
+pub trait Backend {
+    /// Draws the given cells, each cell comes with its x and y position in the gui
+    fn draw(&mut self, cells: &[(i64, i64, Cell)]) -> io::Result<()>;
+
+    /// Clears everything on the backend, not just the gui
+    fn clear(&mut self) -> io::Result<()>;
+
+    fn hide_cursor(&mut self) -> io::Result<()>;
+
+    fn show_cursor(&mut self) -> io::Result<()>;
+
+    /// Makes sure everything drawn so far is visible
+    fn flush(&mut self) -> io::Result<()>;
+
+    /// Sets how many colors the backend can show, backends that dont print colors can ignore this
+    fn set_color_depth(&mut self, _depth: ColorDepth) {}
+
+    /// Switches to a separate screen without scrollback, the original screen comes back when leaving it
+    fn enter_alternate_screen(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn leave_alternate_screen(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Limits scrolling to the rows from top to bottom (inclusive, starting at 0) of the whole backend,
+    /// rows outside of the region stay put when the text in it scrolls
+    fn set_scroll_region(&mut self, _top: i64, _bottom: i64) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Lets the whole backend scroll again
+    fn reset_scroll_region(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Scrolls everything up by the given number of lines, like printing that many new lines at the bottom
+    fn scroll_up(&mut self, _lines: i64) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Sets where the top left corner of the gui is drawn on the backend
+    fn set_origin(&mut self, _x: i64, _y: i64) {}
+
+    /// Makes the gui draw in the given number of rows starting at the line the cursor is on,
+    /// instead of at its origin, so it shows up in the middle of normal output
+    fn enter_inline(&mut self, _height: i64) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Moves the cursor to the line below the inline gui so normal output continues after it
+    fn leave_inline(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+
+
+/*
+    ##### ANSI BACKEND #####
+*/
+
+
+
+// each run of cells starts with a cursor move and colors are only sent when they differ from the previous cell
+// | cursor position | | foreground color          background color   | | character | | clear ansii settings |
+// \x1b[yyy;xxxH       \x1b[38;2;rrr;ggg;bbb;48;2;rrr;ggg;bbbm             c             \x1b[m
+
+// inline guis dont know which row of the terminal they are on, so rows are moved to relative to the cursor
+// | rows up or down | | column |
+// \x1b[yyyA/B         \x1b[xxxG
+
+// the colors depend on the color depth
+// true color:  38;2;rrr;ggg;bbb;48;2;rrr;ggg;bbb
+// 256 colors:  38;5;iii;48;5;iii
+// 16 colors:   3i;4i for the first 8 colors, 9i;10i for the bright ones
+// monochrome:  no color code is sent
+// the default and transparent colors are sent as 39;49 at every depth except monochrome
+
+// text attributes are sent before the colors whenever they change, starting with a reset to turn off the previous ones
+// | reset | | attributes (bold, dim, italic, underline, blink, reverse, strikethrough) |
+// \x1b[0    ;1;2;3;4;5;7;9m
+
+/// Backend that writes ansi escape codes to anything that implements Write
+pub struct AnsiBackend<W: Write> {
+    output: W,
+    color_depth: ColorDepth,
+
+    origin: (i64, i64),
+    scroll_region: Option<(i64, i64)>,
+    inline: Option<Inline>,
+
+    print_buffer: String
+}
+
+struct Inline {
+    height: i64,
+
+    // the row of the gui the cursor is on
+    row: i64
+}
+
+impl AnsiBackend<Stdout> {
+    /// Creates a backend that writes to stdout, using the color depth detected from the environment
+    pub fn stdout() -> Self {
+        let mut backend = Self::new(io::stdout());
+        backend.color_depth = ColorDepth::detect();
+        backend
+    }
+}
+
+impl<W: Write> AnsiBackend<W> {
+    /// Creates a backend that writes true color codes to the output
+    pub fn new(output: W) -> Self {
+        Self {
+            output,
+            color_depth: ColorDepth::TrueColor,
+            origin: (0, 0),
+            scroll_region: None,
+            inline: None,
+            print_buffer: String::new()
+        }
+    }
+
+    pub fn color_depth(&self) -> ColorDepth {
+        self.color_depth
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.output
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.output
+    }
+
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+}
+
+impl<W: Write> Backend for AnsiBackend<W> {
+    fn draw(&mut self, cells: &[(i64, i64, Cell)]) -> io::Result<()> {
+        if cells.is_empty() {
+            return Ok(());
+        }
+
+        self.print_buffer.clear();
+
+        // the cursor is put back where it was so text printed in the scroll region keeps going from there
+        if self.scroll_region.is_some() {
+            self.print_buffer.push_str("\x1b7");
+        }
+
+        let (origin_x, origin_y) = self.origin;
+
+        // where the terminal cursor and colors are after the last cell, None if unknown,
+        // the style starts out empty since every draw ends with a reset
+        let mut cursor = None;
+        let mut colors = None;
+        let mut style = Style::NONE;
+
+        for &(x, y, cell) in cells {
+            // the right half of a wide grapheme is printed along with the left half
+            if cell.c.is_continuation() {
+                continue;
+            }
+
+            // Jump over the cells that werent given
+            if cursor != Some((x, y)) {
+                match &mut self.inline {
+                    None => write!(self.print_buffer, "\x1b[{};{}H", origin_y + y + 1, origin_x + x + 1).unwrap(),
+                    Some(inline) => {
+                        if y > inline.row {
+                            write!(self.print_buffer, "\x1b[{}B", y - inline.row).unwrap();
+                        }
+                        else if y < inline.row {
+                            write!(self.print_buffer, "\x1b[{}A", inline.row - y).unwrap();
+                        }
+
+                        inline.row = y;
+                        write!(self.print_buffer, "\x1b[{}G", origin_x + x + 1).unwrap();
+                    }
+                }
+            }
+
+            if style != cell.style {
+                write_style(&mut self.print_buffer, cell.style);
+                style = cell.style;
+                colors = None; // the reset turned off the colors too
+            }
+
+            if colors != Some((cell.fg, cell.bg)) {
+                write_colors(&mut self.print_buffer, self.color_depth, cell.fg, cell.bg);
+                colors = Some((cell.fg, cell.bg));
+            }
+
+            if cell.c.is_empty() {
+                self.print_buffer.push(' ');
+            }
+            else {
+                self.print_buffer.push_str(cell.c.as_str());
+            }
+
+            cursor = Some((x + cell.c.width(), y));
+        }
+
+        self.print_buffer.push_str("\x1b[m");
+
+        if self.scroll_region.is_some() {
+            self.print_buffer.push_str("\x1b8");
+        }
+
+        self.output.write_all(self.print_buffer.as_bytes())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.output.write_all(b"\x1b[H\x1b[2J")
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.output.write_all(b"\x1b[?25l")
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.output.write_all(b"\x1b[?25h")
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+
+    fn set_color_depth(&mut self, depth: ColorDepth) {
+        self.color_depth = depth;
+    }
+
+    fn enter_alternate_screen(&mut self) -> io::Result<()> {
+        self.output.write_all(b"\x1b[?1049h")
+    }
+
+    fn leave_alternate_screen(&mut self) -> io::Result<()> {
+        self.output.write_all(b"\x1b[?1049l")
+    }
+
+    fn set_scroll_region(&mut self, top: i64, bottom: i64) -> io::Result<()> {
+        self.scroll_region = Some((top, bottom));
+
+        // setting the region moves the cursor to the top left corner, so it is moved to the bottom of the region
+        // where new lines of text would normally go
+        write!(self.output, "\x1b[{};{}r\x1b[{};1H", top + 1, bottom + 1, bottom + 1)
+    }
+
+    fn reset_scroll_region(&mut self) -> io::Result<()> {
+        self.scroll_region = None;
+        self.output.write_all(b"\x1b7\x1b[r\x1b8")
+    }
+
+    fn scroll_up(&mut self, lines: i64) -> io::Result<()> {
+        if lines <= 0 {
+            return Ok(());
+        }
+
+        // new lines at the bottom push the top lines into the scrollback, then the cursor goes back up above them
+        let new_lines = "\n".repeat(lines as usize);
+        write!(self.output, "\x1b[999B{}\x1b[{}A", new_lines, lines)
+    }
+
+    fn set_origin(&mut self, x: i64, y: i64) {
+        self.origin = (x, y);
+    }
+
+    fn enter_inline(&mut self, height: i64) -> io::Result<()> {
+        self.leave_inline()?;
+
+        // new lines make sure there are enough rows below the cursor, scrolling the terminal if there arent,
+        // then the cursor goes back up to the first row of the gui
+        let rows = (height - 1).max(0);
+
+        if rows > 0 {
+            write!(self.output, "{}\x1b[{}A", "\n".repeat(rows as usize), rows)?;
+        }
+
+        self.output.write_all(b"\r")?;
+        self.inline = Some(Inline {height, row: 0});
+
+        Ok(())
+    }
+
+    fn leave_inline(&mut self) -> io::Result<()> {
+        if let Some(inline) = self.inline.take() {
+            let rows = inline.height - 1 - inline.row;
+
+            if rows > 0 {
+                write!(self.output, "\x1b[{}B", rows)?;
+            }
+
+            self.output.write_all(b"\r\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_style(buffer: &mut String, style: Style) {
+    buffer.push_str("\x1b[0");
+
+    for code in style.sgr_codes() {
+        write!(buffer, ";{}", code).unwrap();
+    }
+
+    buffer.push('m');
+}
+
+fn write_colors(buffer: &mut String, depth: ColorDepth, fg: Color, bg: Color) {
+    if depth == ColorDepth::Monochrome {
+        return;
+    }
+
+    buffer.push_str("\x1b[");
+    write_color(buffer, depth, fg, 30);
+    buffer.push(';');
+    write_color(buffer, depth, bg, 40);
+    buffer.push('m');
+}
+
+// base is 30 for the foreground and 40 for the background, the other codes are offset from it
+fn write_color(buffer: &mut String, depth: ColorDepth, color: Color, base: u8) {
+    let rgb = match color.rgb() {
+        Some(rgb) => rgb,
+        None => {
+            // transparent colors only end up here when a cell is drawn without a color, so they look like the default
+            write!(buffer, "{}", base + 9).unwrap();
+            return;
+        }
+    };
+
+    match depth {
+        ColorDepth::TrueColor => write!(buffer, "{};2;{};{};{}", base + 8, rgb.0, rgb.1, rgb.2).unwrap(),
+        ColorDepth::Ansi256 => write!(buffer, "{};5;{}", base + 8, color.to_ansi256().unwrap()).unwrap(),
+        ColorDepth::Ansi16 => {
+            let index = color.to_ansi16().unwrap();
+
+            if index < 8 {
+                write!(buffer, "{}", base + index).unwrap();
+            }
+            else {
+                write!(buffer, "{}", base + 60 + index - 8).unwrap();
+            }
+        },
+        ColorDepth::Monochrome => ()
+    }
+}
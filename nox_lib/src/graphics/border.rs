@@ -0,0 +1,287 @@
+use super::{Canvas, Color, Grapheme};
+
+/// The kind of line a border is drawn with, see Canvas::border
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum BorderStyle {
+    /// ┌─┐
+    #[default]
+    Single,
+    /// ╔═╗
+    Double,
+    /// ╭─╮
+    Rounded,
+    /// ┏━┓
+    Heavy,
+    /// +-+ for terminals and fonts without box drawing characters
+    Ascii
+}
+
+impl BorderStyle {
+    fn weight(&self) -> u8 {
+        match self {
+            Self::Single | Self::Rounded | Self::Ascii => L,
+            Self::Heavy => H,
+            Self::Double => D
+        }
+    }
+}
+
+// the lines going out of the middle of a cell, in the order up, right, down, left
+type Arms = [u8; 4];
+
+const UP: usize = 0;
+const RIGHT: usize = 1;
+const DOWN: usize = 2;
+const LEFT: usize = 3;
+
+// how thick each line is
+const N: u8 = 0; // no line
+const L: u8 = 1; // light
+const H: u8 = 2; // heavy
+const D: u8 = 3; // double
+
+// every box drawing character made of straight lines, rounded corners are handled on their own
+const GLYPHS: [(char, Arms); 109] = [
+    ('─', [N, L, N, L]), ('━', [N, H, N, H]), ('│', [L, N, L, N]), ('┃', [H, N, H, N]),
+    ('┌', [N, L, L, N]), ('┍', [N, H, L, N]), ('┎', [N, L, H, N]), ('┏', [N, H, H, N]),
+    ('┐', [N, N, L, L]), ('┑', [N, N, L, H]), ('┒', [N, N, H, L]), ('┓', [N, N, H, H]),
+    ('└', [L, L, N, N]), ('┕', [L, H, N, N]), ('┖', [H, L, N, N]), ('┗', [H, H, N, N]),
+    ('┘', [L, N, N, L]), ('┙', [L, N, N, H]), ('┚', [H, N, N, L]), ('┛', [H, N, N, H]),
+    ('├', [L, L, L, N]), ('┝', [L, H, L, N]), ('┞', [H, L, L, N]), ('┟', [L, L, H, N]),
+    ('┠', [H, L, H, N]), ('┡', [H, H, L, N]), ('┢', [L, H, H, N]), ('┣', [H, H, H, N]),
+    ('┤', [L, N, L, L]), ('┥', [L, N, L, H]), ('┦', [H, N, L, L]), ('┧', [L, N, H, L]),
+    ('┨', [H, N, H, L]), ('┩', [H, N, L, H]), ('┪', [L, N, H, H]), ('┫', [H, N, H, H]),
+    ('┬', [N, L, L, L]), ('┭', [N, L, L, H]), ('┮', [N, H, L, L]), ('┯', [N, H, L, H]),
+    ('┰', [N, L, H, L]), ('┱', [N, L, H, H]), ('┲', [N, H, H, L]), ('┳', [N, H, H, H]),
+    ('┴', [L, L, N, L]), ('┵', [L, L, N, H]), ('┶', [L, H, N, L]), ('┷', [L, H, N, H]),
+    ('┸', [H, L, N, L]), ('┹', [H, L, N, H]), ('┺', [H, H, N, L]), ('┻', [H, H, N, H]),
+    ('┼', [L, L, L, L]), ('┽', [L, L, L, H]), ('┾', [L, H, L, L]), ('┿', [L, H, L, H]),
+    ('╀', [H, L, L, L]), ('╁', [L, L, H, L]), ('╂', [H, L, H, L]), ('╃', [H, L, L, H]),
+    ('╄', [H, H, L, L]), ('╅', [L, L, H, H]), ('╆', [L, H, H, L]), ('╇', [H, H, L, H]),
+    ('╈', [L, H, H, H]), ('╉', [H, L, H, H]), ('╊', [H, H, H, L]), ('╋', [H, H, H, H]),
+    ('═', [N, D, N, D]), ('║', [D, N, D, N]), ('╒', [N, D, L, N]), ('╓', [N, L, D, N]),
+    ('╔', [N, D, D, N]), ('╕', [N, N, L, D]), ('╖', [N, N, D, L]), ('╗', [N, N, D, D]),
+    ('╘', [L, D, N, N]), ('╙', [D, L, N, N]), ('╚', [D, D, N, N]), ('╛', [L, N, N, D]),
+    ('╜', [D, N, N, L]), ('╝', [D, N, N, D]), ('╞', [L, D, L, N]), ('╟', [D, L, D, N]),
+    ('╠', [D, D, D, N]), ('╡', [L, N, L, D]), ('╢', [D, N, D, L]), ('╣', [D, N, D, D]),
+    ('╤', [N, D, L, D]), ('╥', [N, L, D, L]), ('╦', [N, D, D, D]), ('╧', [L, D, N, D]),
+    ('╨', [D, L, N, L]), ('╩', [D, D, N, D]), ('╪', [L, D, L, D]), ('╫', [D, L, D, L]),
+    ('╬', [D, D, D, D]), ('╴', [N, N, N, L]), ('╵', [L, N, N, N]), ('╶', [N, L, N, N]),
+    ('╷', [N, N, L, N]), ('╸', [N, N, N, H]), ('╹', [H, N, N, N]), ('╺', [N, H, N, N]),
+    ('╻', [N, N, H, N]), ('╼', [N, H, N, L]), ('╽', [L, N, H, N]), ('╾', [N, L, N, H]),
+    ('╿', [H, N, L, N])
+];
+
+const ROUNDED_CORNERS: [(char, Arms); 4] = [
+    ('╭', [N, L, L, N]), ('╮', [N, N, L, L]), ('╯', [L, N, N, L]), ('╰', [L, L, N, N])
+];
+
+// + doesnt say which way its lines go, see plus_arms
+const ASCII: [(char, Arms); 2] = [
+    ('-', [N, L, N, L]), ('|', [L, N, L, N])
+];
+
+// draws the part of a border that goes out of the cell in the directions given (up, right, down, left),
+// joining it with the lines of any border that is already there
+pub(super) fn draw_border_piece<C: Canvas + ?Sized>(canvas: &mut C, x: i64, y: i64, piece: [bool; 4], border_style: BorderStyle, fg: Color, bg: Color) {
+    if piece == [false; 4] {
+        return;
+    }
+
+    let mut arms = cell_arms(canvas, x, y, border_style).unwrap_or([N; 4]);
+
+    for (arm, &in_piece) in arms.iter_mut().zip(&piece) {
+        if in_piece {
+            *arm = border_style.weight();
+        }
+    }
+
+    canvas.pixel(x, y, glyph(arms, border_style), fg, bg);
+}
+
+/// True if the cell has a box drawing character that a border with the given style can be joined with
+pub(super) fn is_border<C: Canvas + ?Sized>(canvas: &C, x: i64, y: i64, border_style: BorderStyle) -> bool {
+    cell_arms(canvas, x, y, border_style).is_some()
+}
+
+// the lines of the character in the cell, None if a border with the given style cant be joined with it
+fn cell_arms<C: Canvas + ?Sized>(canvas: &C, x: i64, y: i64, border_style: BorderStyle) -> Option<Arms> {
+    let grapheme = canvas.cell(x, y)?.c;
+
+    if border_style == BorderStyle::Ascii && grapheme == '+' {
+        return plus_arms(canvas, x, y);
+    }
+
+    arms_of(grapheme, border_style)
+}
+
+// a + goes the way of the ascii lines next to it, so a corner only has two arms and a + in text has none
+fn plus_arms<C: Canvas + ?Sized>(canvas: &C, x: i64, y: i64) -> Option<Arms> {
+    let mut arms = [N; 4];
+
+    for (direction, (dx, dy)) in [(0, -1), (1, 0), (0, 1), (-1, 0)].into_iter().enumerate() {
+        let facing = (direction + 2) % 4; // the arm of the neighbour that points back at the +
+
+        let joined = canvas.cell(x + dx, y + dy).is_some_and(|cell| {
+            cell.c == '+' || arms_of(cell.c, BorderStyle::Ascii).is_some_and(|arms| arms[facing] != N)
+        });
+
+        if joined {
+            arms[direction] = L;
+        }
+    }
+
+    if arms == [N; 4] {None} else {Some(arms)}
+}
+
+// the dashes and bars of ascii borders are only lines when an ascii border is drawn, otherwise they are just text
+fn arms_of(grapheme: Grapheme, border_style: BorderStyle) -> Option<Arms> {
+    let mut chars = grapheme.as_str().chars();
+    let c = chars.next()?;
+
+    if chars.next().is_some() {
+        return None;
+    }
+
+    let ascii: &[(char, Arms)] = if border_style == BorderStyle::Ascii {&ASCII} else {&[]};
+
+    GLYPHS.iter()
+        .chain(&ROUNDED_CORNERS)
+        .chain(ascii)
+        .find(|(glyph, _)| *glyph == c)
+        .map(|&(_, arms)| arms)
+}
+
+// the character with the given lines, or the closest one there is since not every mix of lines has a character
+fn glyph(arms: Arms, border_style: BorderStyle) -> char {
+    let horizontal = arms[LEFT] != N || arms[RIGHT] != N;
+    let vertical = arms[UP] != N || arms[DOWN] != N;
+
+    if border_style == BorderStyle::Ascii {
+        return match (horizontal, vertical) {
+            (true, true) => '+',
+            (true, false) => '-',
+            (false, true) => '|',
+            (false, false) => ' '
+        };
+    }
+
+    // the lines that were already there are switched to the weight of the new border if they cant be mixed
+    let weight = border_style.weight();
+    let reweighted = arms.map(|arm| if arm == N {N} else {weight});
+
+    if border_style == BorderStyle::Rounded {
+        if let Some(&(corner, _)) = ROUNDED_CORNERS.iter().find(|&&(_, corner)| corner == reweighted) {
+            return corner;
+        }
+    }
+
+    // there are no double half lines, so lines that end in the middle of a cell go all the way through it
+    let mut extended = reweighted;
+
+    if horizontal != vertical {
+        let (a, b) = if horizontal {(LEFT, RIGHT)} else {(UP, DOWN)};
+        extended[a] = weight;
+        extended[b] = weight;
+    }
+
+    [arms, reweighted, extended].iter()
+        .find_map(|arms| GLYPHS.iter().find(|(_, glyph_arms)| glyph_arms == arms))
+        .map(|&(glyph, _)| glyph)
+        .unwrap_or(' ')
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::test_backend::test_gui::{gui, text};
+    use crate::graphics::{Gui, TestBackend};
+
+    // a gui with a single line of text on it
+    fn gui_with_text(line: &str) -> Gui<TestBackend> {
+        let mut gui = gui(line.chars().count() as i64, 1);
+        gui.horizontal_text(0, 0, line, Color::WHITE, Color::BLACK);
+        gui
+    }
+
+    #[test]
+    fn boxes_sharing_an_edge_join() {
+        let mut gui = gui(9, 3);
+        gui.border(0, 0, 5, 3, BorderStyle::Single, Color::WHITE, Color::BLACK);
+        gui.border(4, 0, 5, 3, BorderStyle::Single, Color::WHITE, Color::BLACK);
+
+        assert_eq!(text(&mut gui), "┌───┬───┐\n│   │   │\n└───┴───┘");
+    }
+
+    #[test]
+    fn four_boxes_meet_in_a_cross() {
+        let mut gui = gui(5, 5);
+
+        for (x, y) in [(0, 0), (2, 0), (0, 2), (2, 2)] {
+            gui.border(x, y, 3, 3, BorderStyle::Single, Color::WHITE, Color::BLACK);
+        }
+
+        assert_eq!(text(&mut gui), "┌─┬─┐\n│ │ │\n├─┼─┤\n│ │ │\n└─┴─┘");
+    }
+
+    #[test]
+    fn single_line_meets_double_border() {
+        let mut gui = gui(5, 3);
+        gui.border(0, 0, 5, 3, BorderStyle::Double, Color::WHITE, Color::BLACK);
+        gui.horizontal_border(0, 1, 5, BorderStyle::Single, Color::WHITE, Color::BLACK);
+
+        assert_eq!(text(&mut gui), "╔═══╗\n╟───╢\n╚═══╝");
+    }
+
+    #[test]
+    fn line_from_a_rounded_corner() {
+        let mut gui = gui(6, 3);
+        gui.border(0, 0, 4, 3, BorderStyle::Rounded, Color::WHITE, Color::BLACK);
+        gui.horizontal_border(3, 0, 3, BorderStyle::Rounded, Color::WHITE, Color::BLACK);
+
+        // there is no rounded tee so the corner becomes a square one
+        assert_eq!(text(&mut gui), "╭──┬──\n│  │  \n╰──╯  ");
+    }
+
+    #[test]
+    fn ascii_boxes_sharing_an_edge_join() {
+        let mut gui = gui(9, 3);
+        gui.border(0, 0, 5, 3, BorderStyle::Ascii, Color::WHITE, Color::BLACK);
+        gui.border(4, 0, 5, 3, BorderStyle::Ascii, Color::WHITE, Color::BLACK);
+
+        assert_eq!(text(&mut gui), "+---+---+\n|   |   |\n+---+---+");
+    }
+
+    #[test]
+    fn ascii_is_only_joined_by_ascii_borders() {
+        let mut gui = gui(4, 3);
+        gui.border(0, 0, 4, 3, BorderStyle::Ascii, Color::WHITE, Color::BLACK);
+        gui.border(0, 0, 4, 3, BorderStyle::Single, Color::WHITE, Color::BLACK);
+
+        assert_eq!(text(&mut gui), "┌──┐\n│  │\n└──┘");
+
+        let mut gui = gui_with_text("a-b|c");
+        assert!(!is_border(&gui, 1, 0, BorderStyle::Single));
+        assert!(!is_border(&gui, 3, 0, BorderStyle::Heavy));
+        assert!(is_border(&gui, 1, 0, BorderStyle::Ascii));
+
+        // a single line ends on text instead of joining it
+        gui.horizontal_border(1, 0, 3, BorderStyle::Single, Color::WHITE, Color::BLACK);
+        assert_eq!(text(&mut gui), "a───c");
+    }
+
+    #[test]
+    fn plus_goes_the_way_of_the_lines_around_it() {
+        let mut gui = gui(4, 3);
+        gui.border(0, 0, 4, 3, BorderStyle::Ascii, Color::WHITE, Color::BLACK);
+
+        assert_eq!(plus_arms(&gui, 0, 0), Some([N, L, L, N]));
+        assert_eq!(plus_arms(&gui, 3, 2), Some([L, N, N, L]));
+
+        let gui = gui_with_text("1+2");
+        assert_eq!(plus_arms(&gui, 1, 0), None);
+        assert!(!is_border(&gui, 1, 0, BorderStyle::Ascii));
+    }
+}
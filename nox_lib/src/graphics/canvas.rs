@@ -0,0 +1,1288 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::border::{draw_border_piece, is_border};
+use super::{BorderStyle, Cell, Color, Grapheme, Style, View};
+
+/// Something that can be drawn on, the drawing functions are all built on top of draw_cell
+/// so anything that implements it gets the rest for free
+///
+/// Coordinates start at the top left corner, drawing outside of the canvas does nothing
+// the drawing functions take a position, a character and colors, which adds up quickly
+#[allow(clippy::too_many_arguments)]
+pub trait Canvas {
+    fn width(&self) -> i64;
+
+    fn height(&self) -> i64;
+
+    /// Draws a single cell without looking at the cells next to it, transparent colors keep the color
+    /// the cell already had and colors with alpha are blended onto it
+    ///
+    /// Use grapheme or pixel instead, they keep graphemes that take two columns together with their continuation cell
+    fn draw_cell(&mut self, x: i64, y: i64, cell: Cell);
+
+    /// Returns what was drawn at the position, None if it is outside of the canvas
+    fn cell(&self, x: i64, y: i64) -> Option<Cell>;
+
+    /// Draws a grapheme with the given text attributes, the attributes replace the ones the cell had
+    ///
+    /// Graphemes that take two columns also fill the cell to their right with a continuation cell,
+    /// they are replaced with a space if there is no room for them, and any wide grapheme that gets partly drawn over
+    /// is replaced with a space so no half of one is left behind
+    ///
+    /// A space with a foreground color that has alpha below 255 keeps the grapheme and attributes that were there
+    /// and only blends the colors, so translucent overlays and shadows tint the text under them instead of hiding it,
+    /// any other grapheme is drawn with its colors blended so text can fade in and out
+    fn grapheme(&mut self, x: i64, y: i64, grapheme: Grapheme, fg: Color, bg: Color, style: Style) {
+        if x >= self.width() || y >= self.height() || x < 0 || y < 0 {
+            return;
+        }
+
+        if grapheme == ' ' && fg.is_translucent() {
+            if let Some(below) = self.cell(x, y) {
+                self.draw_cell(x, y, Cell::styled(below.c, fg, bg, below.style));
+            }
+
+            return;
+        }
+
+        let wide = grapheme.width() == 2 && x + 1 < self.width();
+
+        let grapheme = if grapheme.width() == 2 && !wide {
+            Grapheme::from(' ')
+        }
+        else {
+            grapheme
+        };
+
+        break_up_wide(self, x, y);
+
+        if wide {
+            break_up_wide(self, x + 1, y);
+        }
+
+        self.draw_cell(x, y, Cell::styled(grapheme, fg, bg, style));
+
+        if wide {
+            self.draw_cell(x + 1, y, Cell::styled(Grapheme::CONTINUATION, fg, bg, style));
+        }
+    }
+
+    /// Draws a character with the given text attributes, the attributes replace the ones the cell had
+    fn pixel_styled(&mut self, x: i64, y: i64, c: char, fg: Color, bg: Color, style: Style) {
+        self.grapheme(x, y, Grapheme::from(c), fg, bg, style);
+    }
+
+    /// Borrows the given rectangle of the canvas as a view with its own coordinates,
+    /// anything drawn on the view is cut off at its edges
+    fn view(&mut self, x: i64, y: i64, width: i64, height: i64) -> View<'_, Self> {
+        View::new(self, x, y, width, height)
+    }
+
+    /// Draws a character without any text attributes, transparent colors keep the color the cell already had
+    /// and colors with alpha are blended onto it, a foreground with alpha keeps the character that was there
+    fn pixel(&mut self, x: i64, y: i64, c: char, fg: Color, bg: Color) {
+        self.pixel_styled(x, y, c, fg, bg, Style::NONE);
+    }
+
+    fn clear(&mut self, c: char, fg: Color, bg: Color) {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                self.pixel(x, y, c, fg, bg);
+            }
+        }
+    }
+
+    fn stroke_rect(&mut self, x: i64, y: i64, width: i64, height: i64, c: char, fg: Color, bg: Color) {
+        for x in x..x+width {
+            self.pixel(x, y, c, fg, bg);
+            self.pixel(x, y+height-1, c, fg, bg);
+        }
+
+        for y in y..y+height {
+            self.pixel(x, y, c, fg, bg);
+            self.pixel(x+width-1, y-1, c, fg, bg);
+        }
+    }
+
+    fn fill_rect(&mut self, x: i64, y: i64, width: i64, height: i64, c: char, fg: Color, bg: Color) {
+        for y in y..y+height {
+            for x in x..x+width {
+                self.pixel(x, y, c, fg, bg);
+            }
+        }
+    }
+
+    fn rect(&mut self, x: i64, y: i64, width: i64, height: i64, stroke_char: char, fill_char: char, stroke_fg: Color, stroke_bg: Color, fill_fg: Color, fill_bg: Color) {
+        self.rect_styled(x, y, width, height, stroke_char, fill_char, stroke_fg, stroke_bg, Style::NONE, fill_fg, fill_bg, Style::NONE);
+    }
+
+    /// Same as rect but the stroke and the fill each get their own text attributes
+    fn rect_styled(&mut self, x: i64, y: i64, width: i64, height: i64, stroke_char: char, fill_char: char, stroke_fg: Color, stroke_bg: Color, stroke_style: Style, fill_fg: Color, fill_bg: Color, fill_style: Style) {
+        for y_coord in 0..height {
+            for x_coord in 0..width {
+                if x_coord == 0 || x_coord == width - 1 || y_coord == 0 || y_coord == height - 1 {
+                    self.pixel_styled(x + x_coord, y + y_coord, stroke_char, stroke_fg, stroke_bg, stroke_style);
+                }
+                else {
+                    self.pixel_styled(x + x_coord, y + y_coord, fill_char, fill_fg, fill_bg, fill_style);
+                }
+            }
+        }
+    }
+
+    /// Draws the outline of a rectangle with box drawing characters,
+    /// where it meets or crosses other borders the lines are joined, so boxes next to each other can share an edge
+    fn border(&mut self, x: i64, y: i64, width: i64, height: i64, border_style: BorderStyle, fg: Color, bg: Color) {
+        let right = x + width - 1;
+        let bottom = y + height - 1;
+
+        for cell_y in y..=bottom {
+            for cell_x in x..=right {
+                let on_side = cell_x == x || cell_x == right;
+                let on_top_or_bottom = cell_y == y || cell_y == bottom;
+
+                if !on_side && !on_top_or_bottom {
+                    continue;
+                }
+
+                // up, right, down, left
+                let piece = [
+                    on_side && cell_y > y,
+                    on_top_or_bottom && cell_x < right,
+                    on_side && cell_y < bottom,
+                    on_top_or_bottom && cell_x > x
+                ];
+
+                draw_border_piece(self, cell_x, cell_y, piece, border_style, fg, bg);
+            }
+        }
+    }
+
+    /// Draws a horizontal line with box drawing characters, the ends join any border they are on,
+    /// like the sides of a box the line splits in two
+    fn horizontal_border(&mut self, x: i64, y: i64, length: i64, border_style: BorderStyle, fg: Color, bg: Color) {
+        let end = x + length - 1;
+
+        for cell_x in x..=end {
+            // ends that arent on a border go all the way through the cell so the line doesnt look short
+            let left = cell_x > x || !is_border(self, cell_x, y, border_style);
+            let right = cell_x < end || !is_border(self, cell_x, y, border_style);
+
+            draw_border_piece(self, cell_x, y, [false, right, false, left], border_style, fg, bg);
+        }
+    }
+
+    /// Draws a vertical line with box drawing characters, the ends join any border they are on
+    fn vertical_border(&mut self, x: i64, y: i64, length: i64, border_style: BorderStyle, fg: Color, bg: Color) {
+        let end = y + length - 1;
+
+        for cell_y in y..=end {
+            let up = cell_y > y || !is_border(self, x, cell_y, border_style);
+            let down = cell_y < end || !is_border(self, x, cell_y, border_style);
+
+            draw_border_piece(self, x, cell_y, [up, false, down, false], border_style, fg, bg);
+        }
+    }
+
+    fn horizontal_text(&mut self, x: i64, y: i64, text: &str, fg: Color, bg: Color) {
+        self.horizontal_text_styled(x, y, text, fg, bg, Style::NONE);
+    }
+
+    /// Draws the text from left to right, graphemes that take two columns move the next one over by two
+    fn horizontal_text_styled(&mut self, x: i64, y: i64, text: &str, fg: Color, bg: Color, style: Style) {
+        let mut x = x;
+
+        for grapheme in text.graphemes(true) {
+            let grapheme = Grapheme::new(grapheme);
+
+            self.grapheme(x, y, grapheme, fg, bg, style);
+            x += grapheme.width();
+        }
+    }
+
+    /// Draws the text from top to bottom, one grapheme per row
+    fn vertical_text(&mut self, x: i64, y: i64, text: &str, fg: Color, bg: Color) {
+        for (i, grapheme) in text.graphemes(true).enumerate() {
+            self.grapheme(x, y + i as i64, Grapheme::new(grapheme), fg, bg, Style::NONE);
+        }
+    }
+
+    fn line(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, c: char, fg: Color, bg: Color) {
+        line_points(x0, y0, x1, y1, |x, y| self.pixel(x, y, c, fg, bg));
+    }
+
+    /// Draws the outline of a circle, the radius is in rows and is doubled sideways
+    /// since cells are about twice as tall as they are wide, so the circle looks round
+    fn circle(&mut self, x: i64, y: i64, radius: i64, c: char, fg: Color, bg: Color) {
+        self.ellipse(x, y, radius * CELL_ASPECT, radius, c, fg, bg);
+    }
+
+    /// Same as circle but filled in
+    fn fill_circle(&mut self, x: i64, y: i64, radius: i64, c: char, fg: Color, bg: Color) {
+        self.fill_ellipse(x, y, radius * CELL_ASPECT, radius, c, fg, bg);
+    }
+
+    /// Draws the outline of an ellipse centered on x and y, the radii are in cells so the ellipse is
+    /// radius_x columns wide and radius_y rows tall on each side of the center
+    fn ellipse(&mut self, x: i64, y: i64, radius_x: i64, radius_y: i64, c: char, fg: Color, bg: Color) { // midpoint ellipse alg
+        for (dx, dy) in ellipse_outline(radius_x, radius_y) {
+            self.pixel(x + dx, y + dy, c, fg, bg);
+        }
+    }
+
+    /// Same as ellipse but filled in
+    fn fill_ellipse(&mut self, x: i64, y: i64, radius_x: i64, radius_y: i64, c: char, fg: Color, bg: Color) {
+        for (dy, half_width) in ellipse_spans(radius_x, radius_y) {
+            for dx in -half_width..=half_width {
+                self.pixel(x + dx, y + dy, c, fg, bg);
+            }
+        }
+    }
+
+    /// Draws the part of the outline of a circle that goes clockwise from the start angle to the end angle,
+    /// the angles are in radians with 0 pointing right, like circle the radius is in rows
+    fn arc(&mut self, x: i64, y: i64, radius: i64, start_angle: f64, end_angle: f64, c: char, fg: Color, bg: Color) {
+        let radius_x = radius * CELL_ASPECT;
+
+        for (dx, dy) in ellipse_outline(radius_x, radius) {
+            if angle_in_range(cell_angle(dx, dy, radius_x, radius), start_angle, end_angle) {
+                self.pixel(x + dx, y + dy, c, fg, bg);
+            }
+        }
+    }
+
+    /// Fills the slice of a circle that goes clockwise from the start angle to the end angle, like a piece of pie
+    fn fill_arc(&mut self, x: i64, y: i64, radius: i64, start_angle: f64, end_angle: f64, c: char, fg: Color, bg: Color) {
+        let radius_x = radius * CELL_ASPECT;
+
+        for (dy, half_width) in ellipse_spans(radius_x, radius) {
+            for dx in -half_width..=half_width {
+                let center = dx == 0 && dy == 0;
+
+                if center || angle_in_range(cell_angle(dx, dy, radius_x, radius), start_angle, end_angle) {
+                    self.pixel(x + dx, y + dy, c, fg, bg);
+                }
+            }
+        }
+    }
+
+    fn triangle(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, x2: i64, y2: i64, c: char, fg: Color, bg: Color) {
+        self.polygon(&[(x0, y0), (x1, y1), (x2, y2)], c, fg, bg);
+    }
+
+    /// Fills a triangle, including the cells its outline would be drawn on
+    fn fill_triangle(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, x2: i64, y2: i64, c: char, fg: Color, bg: Color) {
+        self.fill_polygon(&[(x0, y0), (x1, y1), (x2, y2)], FillRule::NonZero, c, fg, bg);
+    }
+
+    /// Draws lines between the points and from the last point back to the first,
+    /// the corners are only drawn once so colors with alpha dont get blended twice there
+    fn polygon(&mut self, points: &[(i64, i64)], c: char, fg: Color, bg: Color) {
+        if let [(x, y)] = points {
+            self.pixel(*x, *y, c, fg, bg);
+            return;
+        }
+
+        for (i, &(x0, y0)) in points.iter().enumerate() {
+            let (x1, y1) = points[(i + 1) % points.len()];
+
+            if points.len() > 2 || i == 0 {
+                // every edge starts where the one before it ended and the last one ends where the first one started
+                let closing = points.len() > 2 && i == points.len() - 1;
+
+                line_points(x0, y0, x1, y1, |x, y| {
+                    let drawn_before = (i > 0 && (x, y) == (x0, y0)) || (closing && (x, y) == (x1, y1));
+
+                    if !drawn_before {
+                        self.pixel(x, y, c, fg, bg);
+                    }
+                });
+            }
+        }
+    }
+
+    /// Fills the inside of the polygon made by the points, including the cells its outline would be drawn on,
+    /// the fill rule decides which parts are inside when the outline crosses itself
+    ///
+    /// Every cell is only drawn once, so colors with alpha dont get blended twice where edges meet
+    fn fill_polygon(&mut self, points: &[(i64, i64)], rule: FillRule, c: char, fg: Color, bg: Color) { // scanline fill
+        for (y, spans) in polygon_spans(points, rule) {
+            for (start, end) in spans {
+                for x in start..=end {
+                    self.pixel(x, y, c, fg, bg);
+                }
+            }
+        }
+    }
+
+    /// Fills the region of cells around the position that look the same as it, going up, down, left and right,
+    /// like the bucket tool of a paint program
+    fn flood_fill(&mut self, x: i64, y: i64, c: char, fg: Color, bg: Color) {
+        self.flood_fill_with(x, y, Connectivity::Four, RegionMatch::ALL, c, fg, bg);
+    }
+
+    /// Same as flood_fill but with control over which neighbours are part of the region and what has to match
+    fn flood_fill_with(&mut self, x: i64, y: i64, connectivity: Connectivity, matching: RegionMatch, c: char, fg: Color, bg: Color) {
+        for (x, y) in self.region(x, y, connectivity, matching) {
+            self.pixel(x, y, c, fg, bg);
+        }
+    }
+
+    /// Returns the positions of the cells connected to the given one that match it, including the cell itself,
+    /// the set is empty if the position is outside of the canvas
+    fn region(&self, x: i64, y: i64, connectivity: Connectivity, matching: RegionMatch) -> HashSet<(i64, i64)> {
+        let mut region = HashSet::new();
+
+        let start = match self.cell(x, y) {
+            Some(cell) => cell,
+            None => return region
+        };
+
+        let neighbours: &[(i64, i64)] = match connectivity {
+            Connectivity::Four => &[(1, 0), (-1, 0), (0, 1), (0, -1)],
+            Connectivity::Eight => &[(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)]
+        };
+
+        let mut queue = VecDeque::new();
+
+        region.insert((x, y));
+        queue.push_back((x, y));
+
+        while let Some((x, y)) = queue.pop_front() {
+            for &(dx, dy) in neighbours {
+                let neighbour = (x + dx, y + dy);
+
+                if region.contains(&neighbour) {
+                    continue;
+                }
+
+                if self.cell(neighbour.0, neighbour.1).is_some_and(|cell| matching.matches(&start, &cell)) {
+                    region.insert(neighbour);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        region
+    }
+
+    /// Draws a curve from p0 to p2 that is pulled towards p1
+    fn quadratic_bezier(&mut self, p0: (i64, i64), p1: (i64, i64), p2: (i64, i64), c: char, fg: Color, bg: Color) {
+        // a quadratic curve is a cubic one with both control points two thirds of the way to p1
+        let p0 = to_f64(p0);
+        let p1 = to_f64(p1);
+        let p2 = to_f64(p2);
+
+        let c1 = (p0.0 + 2.0 / 3.0 * (p1.0 - p0.0), p0.1 + 2.0 / 3.0 * (p1.1 - p0.1));
+        let c2 = (p2.0 + 2.0 / 3.0 * (p1.0 - p2.0), p2.1 + 2.0 / 3.0 * (p1.1 - p2.1));
+
+        let mut points = vec![p0];
+        flatten_cubic(p0, c1, c2, p2, 0, &mut points);
+
+        draw_polyline(self, &points, c, fg, bg);
+    }
+
+    /// Draws a curve from p0 to p3 that leaves p0 towards p1 and arrives at p3 coming from p2
+    fn cubic_bezier(&mut self, p0: (i64, i64), p1: (i64, i64), p2: (i64, i64), p3: (i64, i64), c: char, fg: Color, bg: Color) {
+        let mut points = vec![to_f64(p0)];
+        flatten_cubic(to_f64(p0), to_f64(p1), to_f64(p2), to_f64(p3), 0, &mut points);
+
+        draw_polyline(self, &points, c, fg, bg);
+    }
+
+    /// Draws a smooth curve that goes through every point in order
+    fn catmull_rom(&mut self, points: &[(i64, i64)], c: char, fg: Color, bg: Color) {
+        if let [(x, y)] = points {
+            self.pixel(*x, *y, c, fg, bg);
+        }
+
+        if points.len() < 2 {
+            return;
+        }
+
+        let mut curve = vec![to_f64(points[0])];
+
+        for i in 0..points.len() - 1 {
+            // the first and last points are repeated so the curve reaches them
+            let p0 = to_f64(points[i.saturating_sub(1)]);
+            let p1 = to_f64(points[i]);
+            let p2 = to_f64(points[i + 1]);
+            let p3 = to_f64(points[(i + 2).min(points.len() - 1)]);
+
+            // each piece of the spline is the same as a cubic bezier curve with these control points
+            let c1 = (p1.0 + (p2.0 - p0.0) / 6.0, p1.1 + (p2.1 - p0.1) / 6.0);
+            let c2 = (p2.0 - (p3.0 - p1.0) / 6.0, p2.1 - (p3.1 - p1.1) / 6.0);
+
+            flatten_cubic(p1, c1, c2, p2, 0, &mut curve);
+        }
+
+        draw_polyline(self, &curve, c, fg, bg);
+    }
+}
+
+// how far a piece of a curve can be from a straight line, in cells, before it is split in two
+const CURVE_TOLERANCE: f64 = 0.25;
+
+// stops splitting curves that never get flat, like ones with points that are far outside of the canvas
+const MAX_CURVE_DEPTH: u32 = 16;
+
+fn to_f64((x, y): (i64, i64)) -> (f64, f64) {
+    (x as f64, y as f64)
+}
+
+// draws lines between the points, rounded to the nearest cell,
+// each line leaves out its start since the line before it already drew it
+fn draw_polyline<C: Canvas + ?Sized>(canvas: &mut C, points: &[(f64, f64)], c: char, fg: Color, bg: Color) {
+    let mut rounded = points.iter().map(|&(x, y)| (x.round() as i64, y.round() as i64));
+
+    let mut previous = match rounded.next() {
+        Some(point) => point,
+        None => return
+    };
+
+    canvas.pixel(previous.0, previous.1, c, fg, bg);
+
+    for point in rounded {
+        if point != previous {
+            line_points(previous.0, previous.1, point.0, point.1, |x, y| {
+                if (x, y) != previous {
+                    canvas.pixel(x, y, c, fg, bg);
+                }
+            });
+
+            previous = point;
+        }
+    }
+}
+
+// splits the curve in half until the pieces are close enough to straight lines, then adds their end points,
+// the start of the curve isnt added since it is the end of the piece before it
+fn flatten_cubic(p0: (f64, f64), p1: (f64, f64), p2: (f64, f64), p3: (f64, f64), depth: u32, points: &mut Vec<(f64, f64)>) { // de casteljau's alg
+    if depth >= MAX_CURVE_DEPTH || (distance_to_segment(p1, p0, p3) <= CURVE_TOLERANCE && distance_to_segment(p2, p0, p3) <= CURVE_TOLERANCE) {
+        points.push(p3);
+        return;
+    }
+
+    let middle = |a: (f64, f64), b: (f64, f64)| ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+
+    let p01 = middle(p0, p1);
+    let p12 = middle(p1, p2);
+    let p23 = middle(p2, p3);
+    let p012 = middle(p01, p12);
+    let p123 = middle(p12, p23);
+    let half = middle(p012, p123);
+
+    flatten_cubic(p0, p01, p012, half, depth + 1, points);
+    flatten_cubic(half, p123, p23, p3, depth + 1, points);
+}
+
+// distance from the point to the closest point between start and end,
+// control points past the ends of the segment pull the curve past them so they arent close to it
+fn distance_to_segment(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let dx = end.0 - start.0;
+    let dy = end.1 - start.1;
+    let length_squared = dx * dx + dy * dy;
+
+    // how far along the segment the closest point is, 0 at start and 1 at end
+    let t = if length_squared == 0.0 {
+        0.0
+    }
+    else {
+        (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length_squared).clamp(0.0, 1.0)
+    };
+
+    ((point.0 - start.0 - t * dx).powi(2) + (point.1 - start.1 - t * dy).powi(2)).sqrt()
+}
+
+/// Which cells count as next to each other when finding a region
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Connectivity {
+    /// Up, down, left and right
+    #[default]
+    Four,
+    /// Up, down, left, right and the diagonals
+    Eight
+}
+
+/// Which parts of a cell have to be the same as the starting cell for it to be part of a region
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct RegionMatch {
+    pub character: bool,
+    pub fg: bool,
+    pub bg: bool
+}
+
+impl RegionMatch {
+    pub const ALL: RegionMatch = RegionMatch {character: true, fg: true, bg: true};
+    pub const CHARACTER: RegionMatch = RegionMatch {character: true, fg: false, bg: false};
+    pub const COLORS: RegionMatch = RegionMatch {character: false, fg: true, bg: true};
+    pub const BACKGROUND: RegionMatch = RegionMatch {character: false, fg: false, bg: true};
+
+    /// True if the cells are the same in every part that has to match
+    pub fn matches(&self, a: &Cell, b: &Cell) -> bool {
+        (!self.character || a.c == b.c)
+            && (!self.fg || a.fg == b.fg)
+            && (!self.bg || a.bg == b.bg)
+    }
+}
+
+/// Decides which parts of a polygon are inside of it when its outline crosses itself
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum FillRule {
+    /// A point is inside if a line from it to the outside crosses the outline an odd number of times,
+    /// so overlapping parts leave holes
+    EvenOdd,
+    /// A point is inside if the outline goes around it at least once, so overlapping parts are filled
+    #[default]
+    NonZero
+}
+
+// the cells covered by a filled polygon as rows of spans (inclusive and sorted), the outline is part of the polygon
+fn polygon_spans(points: &[(i64, i64)], rule: FillRule) -> BTreeMap<i64, Vec<(i64, i64)>> {
+    let mut rows: BTreeMap<i64, Vec<(i64, i64)>> = BTreeMap::new();
+
+    if points.is_empty() {
+        return rows;
+    }
+
+    let edges: Vec<((i64, i64), (i64, i64))> = (0..points.len())
+        .map(|i| (points[i], points[(i + 1) % points.len()]))
+        .collect();
+
+    let top = points.iter().map(|&(_, y)| y).min().unwrap();
+    let bottom = points.iter().map(|&(_, y)| y).max().unwrap();
+
+    // crossings of the edges with the row through the middle of the cells, along with the direction of the edge
+    let mut crossings: Vec<(f64, i64)> = Vec::new();
+
+    for y in top..=bottom {
+        crossings.clear();
+
+        for &((x0, y0), (x1, y1)) in &edges {
+            // the bottom end of each edge is left out so points where two edges meet arent counted twice
+            if y0 == y1 || y < y0.min(y1) || y >= y0.max(y1) {
+                continue;
+            }
+
+            let x = x0 as f64 + (y - y0) as f64 * (x1 - x0) as f64 / (y1 - y0) as f64;
+            crossings.push((x, if y1 > y0 {1} else {-1}));
+        }
+
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut winding = 0;
+
+        for (i, &(x, direction)) in crossings.iter().enumerate() {
+            let inside = match rule {
+                FillRule::EvenOdd => i % 2 == 0,
+                FillRule::NonZero => {
+                    winding += direction;
+                    winding != 0
+                }
+            };
+
+            if let (true, Some(&(next_x, _))) = (inside, crossings.get(i + 1)) {
+                let start = x.ceil() as i64;
+                let end = next_x.floor() as i64;
+
+                if start <= end {
+                    rows.entry(y).or_default().push((start, end));
+                }
+            }
+        }
+    }
+
+    // the outline fills in the edges the scanlines miss, like the bottom row and thin parts
+    for &((x0, y0), (x1, y1)) in &edges {
+        line_points(x0, y0, x1, y1, |x, y| rows.entry(y).or_default().push((x, x)));
+    }
+
+    for spans in rows.values_mut() {
+        *spans = merge_spans(std::mem::take(spans));
+    }
+
+    rows
+}
+
+// sorts the spans and joins the ones that overlap or touch
+fn merge_spans(mut spans: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    spans.sort_unstable();
+
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(spans.len());
+
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end))
+        }
+    }
+
+    merged
+}
+
+// calls plot with every point on the line between the two points
+fn line_points<F: FnMut(i64, i64)>(mut x0: i64, mut y0: i64, mut x1: i64, mut y1: i64, mut plot: F) { // bresenham's line alg
+    let mut dx = x1 - x0;
+    let mut dy = y1 - y0;
+
+    if dy == 0 { // Horizontal
+        if x0 > x1 {
+            std::mem::swap(&mut x0, &mut x1);
+            y0 = y1;
+        }
+
+        for x in x0..=x1 {
+            plot(x, y0);
+        }
+
+        return;
+    }
+    else if dx == 0 { // Vertical
+        if y0 > y1 {
+            x0 = x1;
+            std::mem::swap(&mut y0, &mut y1);
+        }
+
+        for y in y0..=y1 {
+            plot(x0, y);
+        }
+
+        return;
+    }
+    
+    if x0 > x1 { // move everything to the 4 right octants (dx is always positive)
+        std::mem::swap(&mut x0, &mut x1);
+        std::mem::swap(&mut y0, &mut y1);
+
+        dx = x1 - x0;
+        dy = y1 - y0;
+    }
+
+    let loop_x = dy.abs() <= dx.abs();
+
+    if loop_x {
+        if x0 > x1 {
+            std::mem::swap(&mut x0, &mut x1);
+            std::mem::swap(&mut y0, &mut y1);
+
+            dx = x1 - x0;
+            dy = y1 - y0;
+        }
+
+        let res_mod = if dy > 0 {1} else {-1};
+        let mut y = y0;
+        let mut err = -dx;
+        let slope = dy;
+        let dx2 = dx << 1; // bitshift instead of multiply by 2
+
+        for x in x0..=x1 {
+            plot(x, y);
+            err += 2 * slope.abs();
+
+            if err >= 0 {
+                err -= dx2;
+                y += res_mod;
+            }
+        }
+    }
+    else {
+        if y0 > y1 {
+            std::mem::swap(&mut x0, &mut x1);
+            std::mem::swap(&mut y0, &mut y1);
+
+            dx = x1 - x0;
+            dy = y1 - y0;
+        }
+
+        let res_mod = if dx > 0 {1} else {-1};
+        let mut x = x0;
+        let mut err = -dy;
+        let slope = dx;
+        let dy2 = dy << 1; // bitshift instead of multiply by 2
+
+        for y in y0..=y1 {
+            plot(x, y);
+            err += 2 * slope.abs();
+
+            if err >= 0 {
+                err -= dy2;
+                x += res_mod;
+            }
+        }
+    }
+    
+   
+}
+
+
+
+// cells are about twice as tall as they are wide
+const CELL_ASPECT: i64 = 2;
+
+// the points of the ellipse in the bottom right quarter, relative to its center, found with the midpoint ellipse alg
+fn ellipse_quarter(radius_x: i64, radius_y: i64) -> Vec<(i64, i64)> {
+    let mut points = Vec::new();
+
+    if radius_x < 0 || radius_y < 0 {
+        return points;
+    }
+
+    // flat ellipses are just lines, the alg below never gets to the end of them
+    if radius_x == 0 || radius_y == 0 {
+        points.extend((0..=radius_x).flat_map(|x| (0..=radius_y).map(move |y| (x, y))));
+        return points;
+    }
+
+    let rx2 = radius_x * radius_x;
+    let ry2 = radius_y * radius_y;
+
+    let mut x = 0;
+    let mut y = radius_y;
+
+    // how fast x and y change along the curve, the first region ends where the slope goes past -1
+    let mut change_x = 0;
+    let mut change_y = 2 * rx2 * y;
+
+    // the decision values are 4 times the usual ones so they stay whole numbers
+    let mut decision = 4 * ry2 - 4 * rx2 * radius_y + rx2;
+
+    while change_x < change_y {
+        points.push((x, y));
+
+        x += 1;
+        change_x += 2 * ry2;
+
+        if decision < 0 {
+            decision += 4 * (change_x + ry2);
+        }
+        else {
+            y -= 1;
+            change_y -= 2 * rx2;
+            decision += 4 * (change_x - change_y + ry2);
+        }
+    }
+
+    let mut decision = ry2 * (2 * x + 1) * (2 * x + 1) + 4 * rx2 * (y - 1) * (y - 1) - 4 * rx2 * ry2;
+
+    while y >= 0 {
+        points.push((x, y));
+
+        y -= 1;
+        change_y -= 2 * rx2;
+
+        if decision > 0 {
+            decision += 4 * (rx2 - change_y);
+        }
+        else {
+            x += 1;
+            change_x += 2 * ry2;
+            decision += 4 * (change_x - change_y + rx2);
+        }
+    }
+
+    points
+}
+
+// every point of the ellipse outline relative to its center, each point shows up once so colors with alpha arent blended twice
+fn ellipse_outline(radius_x: i64, radius_y: i64) -> Vec<(i64, i64)> {
+    let mut points = Vec::new();
+
+    for (x, y) in ellipse_quarter(radius_x, radius_y) {
+        points.push((x, y));
+
+        if x != 0 {
+            points.push((-x, y));
+        }
+
+        if y != 0 {
+            points.push((x, -y));
+        }
+
+        if x != 0 && y != 0 {
+            points.push((-x, -y));
+        }
+    }
+
+    points.sort_unstable();
+    points.dedup();
+    points
+}
+
+// the rows of a filled ellipse relative to its center, as the row and how far the row goes to each side
+fn ellipse_spans(radius_x: i64, radius_y: i64) -> Vec<(i64, i64)> {
+    if radius_x < 0 || radius_y < 0 {
+        return Vec::new();
+    }
+
+    let mut half_widths = vec![-1; radius_y as usize + 1];
+
+    for (x, y) in ellipse_quarter(radius_x, radius_y) {
+        half_widths[y as usize] = half_widths[y as usize].max(x);
+    }
+
+    let mut spans = Vec::new();
+
+    for (y, &half_width) in half_widths.iter().enumerate() {
+        let y = y as i64;
+
+        spans.push((y, half_width));
+
+        if y != 0 {
+            spans.push((-y, half_width));
+        }
+    }
+
+    spans
+}
+
+// the angle of a point on an ellipse as it looks when the ellipse is drawn as a circle, in radians from 0 to tau
+fn cell_angle(dx: i64, dy: i64, radius_x: i64, radius_y: i64) -> f64 {
+    let angle = (dy as f64 * radius_x as f64).atan2(dx as f64 * radius_y as f64);
+    angle.rem_euclid(std::f64::consts::TAU)
+}
+
+// true if the angle is on the way clockwise from start to end, a full turn or more includes every angle
+fn angle_in_range(angle: f64, start: f64, end: f64) -> bool {
+    use std::f64::consts::TAU;
+
+    if (end - start).abs() >= TAU {
+        return true;
+    }
+
+    (angle - start).rem_euclid(TAU) <= (end - start).rem_euclid(TAU)
+}
+
+// replaces the wide grapheme that covers the cell with a space, does nothing if the cell isnt part of one
+fn break_up_wide<C: Canvas + ?Sized>(canvas: &mut C, x: i64, y: i64) {
+    let cell = match canvas.cell(x, y) {
+        Some(cell) => cell,
+        None => return
+    };
+
+    let space = Cell::new(' ', Color::TRANSPARENT, Color::TRANSPARENT);
+
+    if cell.c.is_continuation() {
+        canvas.draw_cell(x - 1, y, space);
+    }
+    else if cell.c.width() == 2 {
+        canvas.draw_cell(x + 1, y, space);
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::test_backend::test_gui::{dotted_gui, gui, text};
+    use crate::graphics::{Gui, Surface, TestBackend};
+
+    #[test]
+    fn view_uses_its_own_coordinates() {
+        let mut gui = dotted_gui(6, 4);
+        let mut view = gui.view(2, 1, 3, 2);
+
+        assert_eq!((view.width(), view.height(), view.position()), (3, 2, (2, 1)));
+
+        view.pixel(0, 0, 'a', Color::WHITE, Color::BLACK);
+        view.pixel(2, 1, 'b', Color::WHITE, Color::BLACK);
+        assert_eq!(view.cell(0, 0).unwrap().c, 'a');
+
+        assert_eq!(text(&mut gui), "......\n..a...\n....b.\n......");
+    }
+
+    #[test]
+    fn view_cuts_off_drawing_at_every_edge() {
+        let mut gui = dotted_gui(6, 4);
+        let mut view = gui.view(1, 1, 3, 2);
+
+        view.fill_rect(-5, -5, 20, 20, '#', Color::WHITE, Color::BLACK);
+        view.line(-3, 0, 10, 0, '-', Color::WHITE, Color::BLACK);
+
+        for (x, y) in [(-1, 0), (0, -1), (3, 0), (0, 2)] {
+            view.pixel(x, y, 'x', Color::WHITE, Color::BLACK);
+            assert!(view.cell(x, y).is_none(), "cell at ({}, {})", x, y);
+        }
+
+        assert_eq!(text(&mut gui), "......\n.---..\n.###..\n......");
+    }
+
+    #[test]
+    fn wide_grapheme_at_the_right_edge_of_a_view_is_a_space() {
+        let mut gui = dotted_gui(5, 1);
+        gui.view(0, 0, 3, 1).horizontal_text(0, 0, "ab日", Color::WHITE, Color::BLACK);
+
+        assert_eq!(text(&mut gui), "ab ..");
+    }
+
+    #[test]
+    fn nested_views_are_cut_off_by_every_view_around_them() {
+        let mut gui = dotted_gui(8, 4);
+        let mut outer = gui.view(1, 1, 6, 3);
+        let mut inner = outer.view(2, 1, 10, 10);
+
+        inner.fill_rect(0, 0, 10, 10, '#', Color::WHITE, Color::BLACK);
+        assert!(inner.cell(4, 0).is_none());
+        assert_eq!(inner.view(1, 1, 2, 2).position(), (1, 1));
+
+        assert_eq!(text(&mut gui), "........\n........\n...####.\n...####.");
+    }
+
+    #[test]
+    fn view_with_no_size_draws_nothing() {
+        let mut gui = dotted_gui(3, 1);
+        gui.view(0, 0, -2, 1).fill_rect(0, 0, 3, 1, '#', Color::WHITE, Color::BLACK);
+        gui.view(0, 0, 3, 0).fill_rect(0, 0, 3, 1, '#', Color::WHITE, Color::BLACK);
+
+        assert_eq!(text(&mut gui), "...");
+    }
+
+    #[test]
+    fn translucent_overlay_keeps_the_text_below() {
+        let mut gui = gui(6, 2);
+        gui.clear(' ', Color::WHITE, Color::new(0, 0, 200));
+        gui.horizontal_text_styled(0, 0, "hello", Color::WHITE, Color::new(0, 0, 200), Style::BOLD);
+
+        gui.fill_rect(0, 0, 6, 2, ' ', Color::rgba(0, 0, 0, 128), Color::rgba(0, 0, 0, 128));
+
+        assert_eq!(text(&mut gui), "hello \n      ");
+        gui.backend().assert_cell(1, 0, Cell::styled('e', Color::new(127, 127, 127), Color::new(0, 0, 100), Style::BOLD));
+    }
+
+    #[test]
+    fn opaque_and_transparent_colors_replace_the_text() {
+        let mut gui = gui(4, 1);
+        gui.horizontal_text(0, 0, "abcd", Color::WHITE, Color::BLACK);
+
+        gui.pixel(0, 0, 'x', Color::rgba(255, 0, 0, 255), Color::BLACK);
+        gui.pixel(1, 0, ' ', Color::TRANSPARENT, Color::rgba(0, 0, 0, 128));
+
+        assert_eq!(text(&mut gui), "x cd");
+    }
+
+    #[test]
+    fn translucent_text_is_drawn_with_blended_colors() {
+        let mut gui = gui(6, 1);
+        gui.clear(' ', Color::BLACK, Color::BLACK);
+
+        // half way through fading in from the black below
+        gui.horizontal_text(0, 0, "hello", Color::rgba(255, 255, 255, 128), Color::BLACK);
+
+        assert_eq!(text(&mut gui), "hello ");
+        gui.backend().assert_cell(0, 0, Cell::new('h', Color::new(128, 128, 128), Color::BLACK));
+    }
+
+    #[test]
+    fn translucent_wide_grapheme_replaces_the_text_below() {
+        let mut gui = gui(2, 1);
+        gui.horizontal_text(0, 0, "ab", Color::WHITE, Color::BLACK);
+        gui.horizontal_text(0, 0, "日", Color::rgba(255, 0, 0, 1), Color::BLACK);
+
+        assert_eq!(text(&mut gui), "日");
+    }
+
+    #[test]
+    fn translucent_overlay_keeps_wide_graphemes_whole() {
+        let mut gui = gui(4, 1);
+        gui.horizontal_text(0, 0, "日本", Color::WHITE, Color::BLACK);
+
+        gui.pixel(1, 0, ' ', Color::rgba(0, 0, 0, 100), Color::rgba(0, 0, 0, 100));
+
+        assert_eq!(text(&mut gui), "日本");
+    }
+
+    #[test]
+    fn shadow_layer_keeps_the_text_below() {
+        let mut gui = gui(6, 3);
+        gui.clear(' ', Color::WHITE, Color::new(0, 0, 200));
+        gui.horizontal_text(0, 1, "shadow", Color::WHITE, Color::new(0, 0, 200));
+
+        let mut shadow = Surface::new(4, 2);
+        shadow.set_offset(1, 1);
+        shadow.fill_rect(0, 0, 4, 2, ' ', Color::rgba(0, 0, 0, 128), Color::rgba(0, 0, 0, 128));
+        gui.add_layer(shadow);
+
+        assert_eq!(text(&mut gui), "      \nshadow\n      ");
+        gui.backend().assert_bg(1, 1, 4, 2, Color::new(0, 0, 100));
+        gui.backend().assert_fg(1, 1, 4, 1, Color::new(127, 127, 127));
+        gui.backend().assert_bg(0, 1, 1, 1, Color::new(0, 0, 200));
+    }
+
+    #[test]
+    fn ellipse_outline_at_small_radii() {
+        assert_eq!(ellipse_outline(0, 0), [(0, 0)]);
+        assert_eq!(ellipse_outline(1, 1), [(-1, 0), (0, -1), (0, 1), (1, 0)]);
+        assert_eq!(ellipse_outline(2, 1), [(-2, 0), (-1, -1), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 1), (2, 0)]);
+        assert_eq!(ellipse_outline(1, 2), [(-1, -1), (-1, 0), (-1, 1), (0, -2), (0, 2), (1, -1), (1, 0), (1, 1)]);
+        assert!(ellipse_outline(-1, 1).is_empty());
+    }
+
+    #[test]
+    fn flat_ellipses_are_lines() {
+        assert_eq!(ellipse_outline(3, 0), [(-3, 0), (-2, 0), (-1, 0), (0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(ellipse_outline(0, 2), [(0, -2), (0, -1), (0, 0), (0, 1), (0, 2)]);
+
+        assert_eq!(ellipse_spans(3, 0), [(0, 3)]);
+        assert_eq!(ellipse_spans(0, 2), [(0, 0), (1, 0), (-1, 0), (2, 0), (-2, 0)]);
+    }
+
+    #[test]
+    fn ellipse_spans_at_small_radii() {
+        assert_eq!(ellipse_spans(0, 0), [(0, 0)]);
+        assert_eq!(ellipse_spans(1, 1), [(0, 1), (1, 0), (-1, 0)]);
+        assert_eq!(ellipse_spans(2, 1), [(0, 2), (1, 1), (-1, 1)]);
+        assert_eq!(ellipse_spans(1, 2), [(0, 1), (1, 1), (-1, 1), (2, 0), (-2, 0)]);
+        assert!(ellipse_spans(-1, 1).is_empty());
+        assert!(ellipse_spans(1, -1).is_empty());
+    }
+
+    #[test]
+    fn ellipse_spans_cover_the_outline() {
+        for (radius_x, radius_y) in [(1, 1), (2, 1), (1, 2), (5, 3), (3, 7), (8, 4)] {
+            let spans = ellipse_spans(radius_x, radius_y);
+
+            for (x, y) in ellipse_outline(radius_x, radius_y) {
+                let (_, half_width) = spans.iter().find(|(row, _)| *row == y).unwrap();
+                assert!(x.abs() <= *half_width, "({}, {}) isnt filled for radii {} and {}", x, y, radius_x, radius_y);
+            }
+        }
+    }
+
+    #[test]
+    fn arc_only_draws_between_its_angles() {
+        use std::f64::consts::{FRAC_PI_2, PI};
+
+        // clockwise from pointing right to pointing down is the bottom right quarter
+        let mut quarter = gui(9, 5);
+        quarter.clear('.', Color::WHITE, Color::BLACK);
+        quarter.arc(4, 2, 2, 0.0, FRAC_PI_2, '#', Color::WHITE, Color::BLACK);
+        assert_eq!(text(&mut quarter), ".........\n.........\n........#\n.......#.\n....###..");
+
+        // going past 0 wraps around to the right half
+        let mut right = gui(9, 5);
+        right.clear('.', Color::WHITE, Color::BLACK);
+        right.arc(4, 2, 2, 3.0 * FRAC_PI_2, 5.0 * FRAC_PI_2, '#', Color::WHITE, Color::BLACK);
+        assert_eq!(text(&mut right), "....###..\n.......#.\n........#\n.......#.\n....###..");
+
+        // a full turn or more is the whole circle
+        let mut full = gui(9, 5);
+        full.clear('.', Color::WHITE, Color::BLACK);
+        full.arc(4, 2, 2, 1.0, 1.0 + 2.0 * PI, '#', Color::WHITE, Color::BLACK);
+        assert_eq!(text(&mut full), "..#####..\n.#.....#.\n#.......#\n.#.....#.\n..#####..");
+    }
+
+    #[test]
+    fn fill_arc_only_fills_between_its_angles() {
+        let mut gui = gui(9, 5);
+        gui.clear('.', Color::WHITE, Color::BLACK);
+        gui.fill_arc(4, 2, 2, 0.0, std::f64::consts::PI, '#', Color::WHITE, Color::BLACK);
+
+        assert_eq!(text(&mut gui), ".........\n.........\n#########\n.#######.\n..#####..");
+    }
+
+    #[test]
+    fn polygon_corners_are_blended_once() {
+        let mut square = gui(5, 3);
+        square.clear(' ', Color::WHITE, Color::BLACK);
+        square.polygon(&[(0, 0), (4, 0), (4, 2), (0, 2)], ' ', Color::TRANSPARENT, Color::rgba(255, 0, 0, 128));
+
+        let edge = Canvas::cell(&square, 2, 0).unwrap().bg;
+
+        for (x, y) in [(0, 0), (4, 0), (4, 2), (0, 2), (2, 2), (0, 1), (4, 1)] {
+            assert_eq!(Canvas::cell(&square, x, y).unwrap().bg, edge, "cell at ({}, {})", x, y);
+        }
+
+        assert_eq!(Canvas::cell(&square, 2, 1).unwrap().bg, Color::BLACK);
+
+        // a single point is just a pixel
+        let mut point = gui(1, 1);
+        point.clear(' ', Color::WHITE, Color::BLACK);
+        point.polygon(&[(0, 0)], ' ', Color::TRANSPARENT, Color::rgba(255, 0, 0, 128));
+
+        assert_eq!(Canvas::cell(&point, 0, 0).unwrap().bg, Color::new(128, 0, 0));
+    }
+
+    #[test]
+    fn polygon_with_two_points_is_a_line() {
+        let mut gui = gui(4, 1);
+        gui.clear('.', Color::WHITE, Color::BLACK);
+        gui.polygon(&[(0, 0), (3, 0)], '#', Color::WHITE, Color::BLACK);
+
+        assert_eq!(text(&mut gui), "####");
+    }
+
+    // a five pointed star drawn in one go, so the pentagon in the middle is inside it twice
+    const STAR: [(i64, i64); 5] = [(10, 0), (16, 18), (0, 7), (20, 7), (4, 18)];
+
+    fn spans_contain(spans: &BTreeMap<i64, Vec<(i64, i64)>>, x: i64, y: i64) -> bool {
+        spans.get(&y).is_some_and(|row| row.iter().any(|&(start, end)| start <= x && x <= end))
+    }
+
+    #[test]
+    fn even_odd_leaves_the_middle_of_a_star_empty() {
+        let spans = polygon_spans(&STAR, FillRule::EvenOdd);
+
+        assert!(!spans_contain(&spans, 10, 10));
+        assert!(spans_contain(&spans, 10, 3));
+        assert!(spans_contain(&spans, 2, 7));
+        assert!(spans_contain(&spans, 15, 15));
+    }
+
+    #[test]
+    fn nonzero_fills_the_middle_of_a_star() {
+        let spans = polygon_spans(&STAR, FillRule::NonZero);
+
+        assert!(spans_contain(&spans, 10, 10));
+        assert!(spans_contain(&spans, 10, 3));
+        assert!(spans_contain(&spans, 2, 7));
+        assert!(spans_contain(&spans, 15, 15));
+        assert!(!spans_contain(&spans, 10, 17)); // between the bottom points
+    }
+
+    #[test]
+    fn horizontal_edges_are_filled() {
+        let rectangle = polygon_spans(&[(0, 0), (4, 0), (4, 2), (0, 2)], FillRule::NonZero);
+        assert_eq!(rectangle, BTreeMap::from([(0, vec![(0, 4)]), (1, vec![(0, 4)]), (2, vec![(0, 4)])]));
+
+        // the step in the middle is a horizontal edge that isnt at the top or bottom
+        let step = polygon_spans(&[(0, 0), (2, 0), (2, 2), (4, 2), (4, 4), (0, 4)], FillRule::EvenOdd);
+        assert_eq!(step, BTreeMap::from([
+            (0, vec![(0, 2)]),
+            (1, vec![(0, 2)]),
+            (2, vec![(0, 4)]),
+            (3, vec![(0, 4)]),
+            (4, vec![(0, 4)])
+        ]));
+
+        let flat = polygon_spans(&[(1, 3), (5, 3)], FillRule::NonZero);
+        assert_eq!(flat, BTreeMap::from([(3, vec![(1, 5)])]));
+    }
+
+    // a diagonal line of #, split in two halves that only touch at the corners of the cells
+    fn diagonal_wall() -> Gui<TestBackend> {
+        let mut gui = gui(4, 4);
+        gui.clear('.', Color::WHITE, Color::BLACK);
+        gui.line(0, 3, 3, 0, '#', Color::WHITE, Color::BLACK);
+        gui
+    }
+
+    #[test]
+    fn four_connectivity_stops_at_diagonal_walls() {
+        let mut gui = diagonal_wall();
+        gui.flood_fill_with(0, 0, Connectivity::Four, RegionMatch::ALL, 'o', Color::WHITE, Color::BLACK);
+
+        assert_eq!(text(&mut gui), "ooo#\noo#.\no#..\n#...");
+    }
+
+    #[test]
+    fn eight_connectivity_goes_through_diagonal_gaps() {
+        let mut gui = diagonal_wall();
+        gui.flood_fill_with(0, 0, Connectivity::Eight, RegionMatch::ALL, 'o', Color::WHITE, Color::BLACK);
+
+        assert_eq!(text(&mut gui), "ooo#\noo#o\no#oo\n#ooo");
+    }
+
+    #[test]
+    fn diagonal_cells_are_only_a_region_with_eight_connectivity() {
+        let mut gui = gui(3, 3);
+        gui.clear('.', Color::WHITE, Color::BLACK);
+        gui.line(0, 0, 2, 2, '#', Color::WHITE, Color::BLACK);
+
+        assert_eq!(gui.region(0, 0, Connectivity::Four, RegionMatch::ALL), HashSet::from([(0, 0)]));
+        assert_eq!(gui.region(0, 0, Connectivity::Eight, RegionMatch::ALL), HashSet::from([(0, 0), (1, 1), (2, 2)]));
+    }
+
+    #[test]
+    fn region_match_decides_what_has_to_be_the_same() {
+        let mut gui = gui(5, 1);
+        gui.pixel(0, 0, 'a', Color::WHITE, Color::BLACK);
+        gui.pixel(1, 0, 'a', Color::RED, Color::BLACK); // different fg
+        gui.pixel(2, 0, 'b', Color::WHITE, Color::BLACK); // different character
+        gui.pixel(3, 0, 'a', Color::WHITE, Color::BLUE); // different bg
+        gui.pixel(4, 0, 'a', Color::WHITE, Color::BLACK);
+
+        let region = |matching| gui.region(0, 0, Connectivity::Four, matching);
+
+        assert_eq!(region(RegionMatch::ALL), HashSet::from([(0, 0)]));
+        assert_eq!(region(RegionMatch::CHARACTER), HashSet::from([(0, 0), (1, 0)]));
+        assert_eq!(region(RegionMatch::COLORS), HashSet::from([(0, 0)]));
+        assert_eq!(region(RegionMatch::BACKGROUND), HashSet::from([(0, 0), (1, 0), (2, 0)]));
+
+        let anything = RegionMatch {character: false, fg: false, bg: false};
+        assert_eq!(region(anything).len(), 5);
+
+        assert_eq!(gui.region(4, 0, Connectivity::Four, RegionMatch::CHARACTER), HashSet::from([(3, 0), (4, 0)]));
+    }
+
+    #[test]
+    fn region_outside_the_canvas_is_empty() {
+        let mut gui = gui(3, 2);
+        gui.clear('.', Color::WHITE, Color::BLACK);
+
+        for (x, y) in [(-1, 0), (0, -1), (3, 0), (0, 2)] {
+            assert!(gui.region(x, y, Connectivity::Eight, RegionMatch::ALL).is_empty());
+            gui.flood_fill(x, y, 'o', Color::WHITE, Color::BLACK);
+        }
+
+        assert_eq!(text(&mut gui), "...\n...");
+    }
+
+    #[test]
+    fn curves_reach_their_end_points() {
+        let mut gui = gui(20, 10);
+        gui.quadratic_bezier((1, 8), (10, -5), (18, 7), '#', Color::WHITE, Color::BLACK);
+        gui.cubic_bezier((0, 0), (15, 9), (3, 9), (19, 1), '#', Color::WHITE, Color::BLACK);
+        gui.catmull_rom(&[(2, 3), (7, 9), (12, 2), (17, 5)], '#', Color::WHITE, Color::BLACK);
+        gui.display().unwrap();
+
+        for (x, y) in [(1, 8), (18, 7), (0, 0), (19, 1), (2, 3), (7, 9), (12, 2), (17, 5)] {
+            assert_eq!(gui.backend().cell(x, y).unwrap().c, Grapheme::from('#'), "cell at ({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn curve_overshoots_past_its_end_points() {
+        // the control points are on the line through the ends but the curve goes past both of them
+        let mut gui = gui(20, 1);
+        gui.clear('.', Color::WHITE, Color::BLACK);
+        gui.cubic_bezier((4, 0), (44, 0), (-26, 0), (14, 0), '#', Color::WHITE, Color::BLACK);
+
+        // from 4 the curve goes back to about 1, then out to about 17 before coming back to 14
+        assert_eq!(text(&mut gui), ".#################..");
+    }
+
+    #[test]
+    fn curve_joints_are_blended_once() {
+        let mut gui = gui(12, 6);
+        gui.clear(' ', Color::WHITE, Color::BLACK);
+        gui.cubic_bezier((0, 0), (14, 0), (14, 5), (0, 5), ' ', Color::TRANSPARENT, Color::rgba(255, 0, 0, 128));
+        gui.display().unwrap();
+
+        let colors: HashSet<Color> = (0..6)
+            .flat_map(|y| (0..12).map(move |x| (x, y)))
+            .map(|(x, y)| gui.backend().cell(x, y).unwrap().bg)
+            .collect();
+
+        assert_eq!(colors, HashSet::from([Color::BLACK, Color::new(128, 0, 0)]));
+    }
+
+    #[test]
+    fn curve_splitting_stops_at_the_depth_limit() {
+        // control points this far out never get close enough to the ends to count as flat
+        let mut points = vec![(0.0, 0.0)];
+        flatten_cubic((0.0, 0.0), (1e15, 1e15), (-1e15, 1e15), (10.0, 0.0), 0, &mut points);
+
+        assert_eq!(points.len(), (1 << MAX_CURVE_DEPTH) + 1);
+        assert_eq!(points.last(), Some(&(10.0, 0.0)));
+    }
+
+    #[test]
+    fn distance_to_segment_is_clamped_to_its_ends() {
+        assert_eq!(distance_to_segment((5.0, 3.0), (0.0, 0.0), (10.0, 0.0)), 3.0);
+        assert_eq!(distance_to_segment((14.0, 3.0), (0.0, 0.0), (10.0, 0.0)), 5.0);
+        assert_eq!(distance_to_segment((-3.0, -4.0), (0.0, 0.0), (10.0, 0.0)), 5.0);
+        assert_eq!(distance_to_segment((3.0, 4.0), (0.0, 0.0), (0.0, 0.0)), 5.0);
+    }
+}
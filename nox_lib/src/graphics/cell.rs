@@ -0,0 +1,20 @@
+use super::{Color, Grapheme, Style};
+
+/// A single grapheme on the screen along with its colors and text attributes
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Cell {
+    pub c: Grapheme,
+    pub fg: Color,
+    pub bg: Color,
+    pub style: Style
+}
+
+impl Cell {
+    pub fn new(c: impl Into<Grapheme>, fg: Color, bg: Color) -> Self {
+        Self {c: c.into(), fg, bg, style: Style::NONE}
+    }
+
+    pub fn styled(c: impl Into<Grapheme>, fg: Color, bg: Color, style: Style) -> Self {
+        Self {c: c.into(), fg, bg, style}
+    }
+}
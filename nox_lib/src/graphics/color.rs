@@ -1,32 +1,349 @@
-#[derive(Clone, Copy, PartialEq)]
-pub struct Color {
-    pub r: u8,
-    pub g: u8,
-    pub b: u8
+/// The color of a character or of the background behind it
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Color {
+    /// A 24 bit color
+    Rgb(u8, u8, u8),
+    /// A 24 bit color with an alpha channel, it is blended with the color below it when drawn,
+    /// 0 is fully see through and 255 is the same as Rgb
+    Rgba(u8, u8, u8, u8),
+    /// Whatever color the terminal uses by default, which depends on the users theme
+    TerminalDefault,
+    /// Keeps the color that is already there when drawing, the backend shows it as the default color
+    Transparent
 }
 
 impl Color {
     pub fn new(r: u8, g: u8, b: u8) -> Self {
-        Self {r, g, b}
+        Self::Rgb(r, g, b)
     }
+
+    pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self::Rgba(r, g, b, a)
+    }
+
+    /// Returns the red, green and blue channels, None for the default and transparent colors
+    pub fn rgb(&self) -> Option<(u8, u8, u8)> {
+        match *self {
+            Self::Rgb(r, g, b) | Self::Rgba(r, g, b, _) => Some((r, g, b)),
+            _ => None
+        }
+    }
+
+    /// True for colors with an alpha below 255, the ones that let what is below them show through
+    pub fn is_translucent(&self) -> bool {
+        matches!(*self, Self::Rgba(_, _, _, alpha) if alpha < 255)
+    }
+
+    /// Returns the same color with the given alpha, the default and transparent colors dont have one and are returned as they are
+    pub fn with_alpha(&self, alpha: u8) -> Self {
+        match self.rgb() {
+            Some((r, g, b)) => Self::Rgba(r, g, b, alpha),
+            None => *self
+        }
+    }
+
+    /// Returns the color that is seen when this color is drawn over the given one,
+    /// the default color below is treated as black since there is no way to know what the terminal shows for it
+    ///
+    /// Transparent colors leave the color below as it is and the default color always replaces it,
+    /// nothing is mixed when the color below is transparent so colors with alpha keep it to be blended later
+    pub fn blend(&self, below: Color, mode: BlendMode) -> Color {
+        let (r, g, b, alpha) = match *self {
+            Self::Transparent => return below,
+            Self::TerminalDefault => return Self::TerminalDefault,
+            Self::Rgb(r, g, b) => (r, g, b, 255),
+            Self::Rgba(r, g, b, a) => (r, g, b, a)
+        };
+
+        if below == Self::Transparent {
+            return *self;
+        }
+
+        let below = below.rgb().unwrap_or((0, 0, 0));
+
+        let channel = |top: u8, below: u8| {
+            let top = top as u32;
+            let below = below as u32;
+
+            let mixed = match mode {
+                BlendMode::Normal => top,
+                BlendMode::Multiply => top * below / 255,
+                BlendMode::Screen => 255 - (255 - top) * (255 - below) / 255,
+                BlendMode::Add => (top + below).min(255)
+            };
+
+            ((below * (255 - alpha as u32) + mixed * alpha as u32 + 127) / 255) as u8
+        };
+
+        Self::Rgb(channel(r, below.0), channel(g, below.1), channel(b, below.2))
+    }
+
+    /// Returns the color that is seen when this color is drawn over the given one on a surface, like blend in normal mode
+    /// except that a translucent color over a translucent one stays translucent, with the alpha of the two together,
+    /// so it can still be blended with the layers below the surface
+    pub fn over(&self, below: Color) -> Color {
+        let ((r, g, b, alpha), (below_r, below_g, below_b, below_alpha)) = match (*self, below) {
+            (Self::Rgba(r, g, b, alpha), Self::Rgba(below_r, below_g, below_b, below_alpha)) => {
+                ((r, g, b, alpha as u32), (below_r, below_g, below_b, below_alpha as u32))
+            },
+            _ => return self.blend(below, BlendMode::Normal)
+        };
+
+        // how much of the color below still shows through this one
+        let below_weight = (below_alpha * (255 - alpha) + 127) / 255;
+        let combined_alpha = alpha + below_weight;
+
+        if combined_alpha == 0 {
+            return *self;
+        }
+
+        let channel = |top: u8, below: u8| ((top as u32 * alpha + below as u32 * below_weight + combined_alpha / 2) / combined_alpha) as u8;
+        let (r, g, b) = (channel(r, below_r), channel(g, below_g), channel(b, below_b));
+
+        if combined_alpha == 255 {
+            Self::Rgb(r, g, b)
+        }
+        else {
+            Self::Rgba(r, g, b, combined_alpha as u8)
+        }
+    }
+
+    /// Returns the color of the given xterm 256 color palette index
+    pub fn from_ansi256(index: u8) -> Self {
+        match index {
+            0..=15 => ANSI16_PALETTE[index as usize],
+            16..=231 => {
+                let index = index - 16;
+                Self::new(CUBE_LEVELS[(index / 36) as usize], CUBE_LEVELS[(index / 6 % 6) as usize], CUBE_LEVELS[(index % 6) as usize])
+            },
+            232..=255 => {
+                let level = 8 + 10 * (index - 232);
+                Self::new(level, level, level)
+            }
+        }
+    }
+
+    /// Returns the index of the closest color in the xterm 256 color palette, None for the default and transparent colors,
+    /// only the color cube and the grayscale ramp are considered since the first 16 colors depend on the terminal theme
+    pub fn to_ansi256(&self) -> Option<u8> {
+        let (r, g, b) = self.rgb()?;
+
+        let cube_index = |channel: u8| CUBE_LEVELS.iter()
+            .enumerate()
+            .min_by_key(|(_, &level)| (level as i32 - channel as i32).abs())
+            .unwrap().0 as u8;
+
+        let cube = 16 + 36 * cube_index(r) + 6 * cube_index(g) + cube_index(b);
+
+        let average = (r as u16 + g as u16 + b as u16) / 3;
+        let gray = 232 + ((average.saturating_sub(3) / 10).min(23)) as u8;
+
+        if distance((r, g, b), Self::from_ansi256(gray).rgb()?) < distance((r, g, b), Self::from_ansi256(cube).rgb()?) {
+            Some(gray)
+        }
+        else {
+            Some(cube)
+        }
+    }
+
+    /// Returns the index of the closest of the 16 standard terminal colors, None for the default and transparent colors
+    pub fn to_ansi16(&self) -> Option<u8> {
+        let rgb = self.rgb()?;
+        (0..16).min_by_key(|&index| distance(rgb, ANSI16_PALETTE[index as usize].rgb().unwrap()))
+    }
+}
+
+// squared distance between the colors, weighted roughly by how sensitive eyes are to each channel
+fn distance((r0, g0, b0): (u8, u8, u8), (r1, g1, b1): (u8, u8, u8)) -> u32 {
+    let dr = r0 as i32 - r1 as i32;
+    let dg = g0 as i32 - g1 as i32;
+    let db = b0 as i32 - b1 as i32;
+
+    (3 * dr * dr + 4 * dg * dg + 2 * db * db) as u32
 }
 
+/// How a color is combined with the color below it when it is drawn
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum BlendMode {
+    /// The color replaces the one below it
+    #[default]
+    Normal,
+    /// Multiplies the colors, which always makes things darker, good for shadows
+    Multiply,
+    /// The opposite of multiply, which always makes things lighter
+    Screen,
+    /// Adds the colors together, good for glows
+    Add
+}
+
+/// How many colors the output supports, colors are mapped to the closest one available
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorDepth {
+    /// 24 bit rgb colors
+    TrueColor,
+    /// The xterm 256 color palette
+    Ansi256,
+    /// The 16 standard terminal colors
+    Ansi16,
+    /// No colors at all
+    Monochrome
+}
+
+impl ColorDepth {
+    /// Guesses the color depth of the terminal from the NO_COLOR, COLORTERM and TERM environment variables
+    pub fn detect() -> Self {
+        let no_color = std::env::var_os("NO_COLOR").map(|value| value.to_string_lossy().into_owned());
+        let colorterm = std::env::var("COLORTERM").ok();
+        let term = std::env::var("TERM").ok();
+
+        Self::from_env_values(no_color.as_deref(), colorterm.as_deref(), term.as_deref())
+    }
+
+    /// Guesses the color depth from the values of NO_COLOR, COLORTERM and TERM, None for the ones that arent set
+    pub fn from_env_values(no_color: Option<&str>, colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if no_color.is_some_and(|value| !value.is_empty()) {
+            return Self::Monochrome;
+        }
+
+        if let Some("truecolor" | "24bit") = colorterm {
+            return Self::TrueColor;
+        }
+
+        let term = match term {
+            Some(term) => term,
+            None => return Self::Monochrome
+        };
+
+        if term.is_empty() || term == "dumb" {
+            Self::Monochrome
+        }
+        else if term.contains("truecolor") || term.contains("direct") {
+            Self::TrueColor
+        }
+        else if term.contains("256color") {
+            Self::Ansi256
+        }
+        else {
+            Self::Ansi16
+        }
+    }
+}
+
+// the colors xterm uses for the 16 standard colors
+const ANSI16_PALETTE: [Color; 16] = [
+    Color::Rgb(  0,   0,   0), Color::Rgb(205,   0,   0), Color::Rgb(  0, 205,   0), Color::Rgb(205, 205,   0),
+    Color::Rgb(  0,   0, 238), Color::Rgb(205,   0, 205), Color::Rgb(  0, 205, 205), Color::Rgb(229, 229, 229),
+    Color::Rgb(127, 127, 127), Color::Rgb(255,   0,   0), Color::Rgb(  0, 255,   0), Color::Rgb(255, 255,   0),
+    Color::Rgb( 92,  92, 255), Color::Rgb(255,   0, 255), Color::Rgb(  0, 255, 255), Color::Rgb(255, 255, 255)
+];
+
+// the channel values of the 6x6x6 color cube in the 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
 // Constants
 impl Color {
     // Core 8 colors
-    pub const   WHITE: Color = Color {r: 255, g: 255, b: 255};
-    pub const   BLACK: Color = Color {r:   0, g:   0, b:   0};
-    pub const     RED: Color = Color {r: 255, g:   0, b:   0};
-    pub const   GREEN: Color = Color {r:   0, g: 255, b:   0};
-    pub const    BLUE: Color = Color {r:   0, g:   0, b: 255};
-    pub const    CYAN: Color = Color {r:   0, g: 255, b: 255};
-    pub const MAGENTA: Color = Color {r: 255, g:   0, b: 255};
-    pub const  YELLOW: Color = Color {r: 255, g: 255, b:   0};
+    pub const   WHITE: Color = Color::Rgb(255, 255, 255);
+    pub const   BLACK: Color = Color::Rgb(  0,   0,   0);
+    pub const     RED: Color = Color::Rgb(255,   0,   0);
+    pub const   GREEN: Color = Color::Rgb(  0, 255,   0);
+    pub const    BLUE: Color = Color::Rgb(  0,   0, 255);
+    pub const    CYAN: Color = Color::Rgb(  0, 255, 255);
+    pub const MAGENTA: Color = Color::Rgb(255,   0, 255);
+    pub const  YELLOW: Color = Color::Rgb(255, 255,   0);
 
     // Common Extras
-    pub const  ORANGE: Color = Color {r: 255, g: 127, b:   0};
-    pub const  PURPLE: Color = Color {r: 127, g:   0, b: 255};
+    pub const  ORANGE: Color = Color::Rgb(255, 127,   0);
+    pub const  PURPLE: Color = Color::Rgb(127,   0, 255);
 
     // Special
-    pub const DEFAULT: Color = Color {r:   0, g:   0, b:   0};
-}
\ No newline at end of file
+    /// Leaves the color that is already there, like it always did, the same as TRANSPARENT
+    pub const          DEFAULT: Color = Color::Transparent;
+    pub const      TRANSPARENT: Color = Color::Transparent;
+    /// Resets the color to the one the terminal uses by default
+    pub const TERMINAL_DEFAULT: Color = Color::TerminalDefault;
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ansi256_indices() {
+        let cases = [
+            (Color::RED, 196),
+            (Color::GREEN, 46),
+            (Color::BLUE, 21),
+            (Color::BLACK, 16),
+            (Color::WHITE, 231),
+            (Color::ORANGE, 208),
+            (Color::new(95, 135, 175), 67),
+
+            // grays go to the grayscale ramp unless the cube has the same gray
+            (Color::new(8, 8, 8), 232),
+            (Color::new(100, 100, 100), 241),
+            (Color::new(128, 128, 128), 244),
+            (Color::new(130, 128, 126), 244),
+            (Color::new(238, 238, 238), 255),
+            (Color::new(135, 135, 135), 102)
+        ];
+
+        for (color, expected) in cases {
+            assert_eq!(color.to_ansi256(), Some(expected), "{:?}", color);
+        }
+
+        assert_eq!(Color::TERMINAL_DEFAULT.to_ansi256(), None);
+        assert_eq!(Color::TRANSPARENT.to_ansi256(), None);
+    }
+
+    #[test]
+    fn ansi16_indices() {
+        for (index, color) in ANSI16_PALETTE.iter().enumerate() {
+            assert_eq!(color.to_ansi16(), Some(index as u8), "{:?}", color);
+        }
+
+        let cases = [
+            (Color::RED, 9),
+            (Color::BLUE, 4),
+            (Color::new(120, 120, 120), 8),
+            (Color::new(200, 200, 200), 7),
+            (Color::new(10, 10, 10), 0)
+        ];
+
+        for (color, expected) in cases {
+            assert_eq!(color.to_ansi16(), Some(expected), "{:?}", color);
+        }
+
+        assert_eq!(Color::TERMINAL_DEFAULT.to_ansi16(), None);
+        assert_eq!(Color::TRANSPARENT.to_ansi16(), None);
+    }
+
+    #[test]
+    fn color_depth_from_env_values() {
+        let cases = [
+            ((None, None, Some("xterm-256color")), ColorDepth::Ansi256),
+            ((None, None, Some("xterm")), ColorDepth::Ansi16),
+            ((None, None, Some("linux")), ColorDepth::Ansi16),
+            ((None, None, Some("xterm-direct")), ColorDepth::TrueColor),
+            ((None, None, Some("foot-truecolor")), ColorDepth::TrueColor),
+            ((None, None, Some("dumb")), ColorDepth::Monochrome),
+            ((None, None, Some("")), ColorDepth::Monochrome),
+            ((None, None, None), ColorDepth::Monochrome),
+
+            // COLORTERM wins over TERM
+            ((None, Some("truecolor"), Some("xterm")), ColorDepth::TrueColor),
+            ((None, Some("24bit"), None), ColorDepth::TrueColor),
+            ((None, Some("yes"), Some("xterm-256color")), ColorDepth::Ansi256),
+
+            // NO_COLOR wins over everything unless it is empty
+            ((Some("1"), Some("truecolor"), Some("xterm-256color")), ColorDepth::Monochrome),
+            ((Some(""), None, Some("xterm-256color")), ColorDepth::Ansi256)
+        ];
+
+        for ((no_color, colorterm, term), expected) in cases {
+            assert_eq!(ColorDepth::from_env_values(no_color, colorterm, term), expected, "NO_COLOR={:?} COLORTERM={:?} TERM={:?}", no_color, colorterm, term);
+        }
+    }
+}
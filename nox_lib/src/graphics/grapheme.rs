@@ -0,0 +1,113 @@
+use std::fmt;
+
+use unicode_width::UnicodeWidthStr;
+
+// how many bytes of utf-8 fit in a grapheme, enough for accented letters, flags and most emoji sequences,
+// it keeps a grapheme and its length at 32 bytes
+const CAPACITY: usize = 31;
+
+// the length of the continuation cell, real graphemes are never this long
+const CONTINUATION_LEN: u8 = u8::MAX;
+
+/// What is shown in a single cell, a user perceived character that can be made up of several chars
+/// like a letter with combining accents or an emoji sequence
+///
+/// Graphemes that take two columns, like cjk characters and most emoji, are followed by a continuation cell
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Grapheme {
+    bytes: [u8; CAPACITY],
+    len: u8
+}
+
+impl Grapheme {
+    /// A cell with nothing in it, surfaces use it for cells that show what is below them
+    pub const NONE: Grapheme = Grapheme {bytes: [0; CAPACITY], len: 0};
+
+    /// The right half of a grapheme that takes two columns
+    pub const CONTINUATION: Grapheme = Grapheme {bytes: [0; CAPACITY], len: CONTINUATION_LEN};
+
+    /// Creates a grapheme out of the text, which should be a single grapheme cluster,
+    /// clusters that are too long to store only keep their first char
+    pub fn new(text: &str) -> Self {
+        if text.len() > CAPACITY {
+            return text.chars().next().map(Self::from).unwrap_or(Self::NONE);
+        }
+
+        let mut bytes = [0; CAPACITY];
+        bytes[..text.len()].copy_from_slice(text.as_bytes());
+
+        Self {bytes, len: text.len() as u8}
+    }
+
+    pub fn as_str(&self) -> &str {
+        if self.is_continuation() {
+            return "";
+        }
+
+        // the bytes always come from a str
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or("")
+    }
+
+    /// True for NONE, which is also what '\0' turns into
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_continuation(&self) -> bool {
+        self.len == CONTINUATION_LEN
+    }
+
+    /// How many columns the grapheme takes, either 1 or 2,
+    /// empty graphemes, control characters and lone combining marks still get a column of their own
+    ///
+    /// Continuation cells dont take any columns since they belong to the grapheme before them
+    pub fn width(&self) -> i64 {
+        if self.is_continuation() {
+            return 0;
+        }
+
+        self.as_str().width().clamp(1, 2) as i64
+    }
+}
+
+impl From<char> for Grapheme {
+    fn from(c: char) -> Self {
+        if c == '\0' {
+            return Self::NONE;
+        }
+
+        let mut bytes = [0; CAPACITY];
+        let len = c.encode_utf8(&mut bytes).len();
+
+        Self {bytes, len: len as u8}
+    }
+}
+
+impl From<&str> for Grapheme {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl PartialEq<char> for Grapheme {
+    fn eq(&self, other: &char) -> bool {
+        *self == Self::from(*other)
+    }
+}
+
+impl fmt::Debug for Grapheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_continuation() {
+            write!(f, "Grapheme(CONTINUATION)")
+        }
+        else {
+            write!(f, "Grapheme({:?})", self.as_str())
+        }
+    }
+}
+
+impl fmt::Display for Grapheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -1,91 +1,84 @@
-use std::io::prelude::*;
+use std::io::{self, Stdout};
 
-use super::Color;
+use crate::io::{MouseEvent, Term};
 
-// each character is in the format of
-// | foreground color   |  | background color   | | character | | clear ansii settings |
-// \x1b[38;2;rrr;ggg;bbbm  \x1b[48;2;rrr;ggg;bbbm       c             \x1b[m
+use super::{AnsiBackend, Backend, BlendMode, Canvas, Cell, Color, ColorDepth, Grapheme, LayerId, Style, Surface};
 
-// all together
-// \x1b[38;2;rrr;ggg;bbbm\x1b[48;2;rrr;ggg;bbbmc\x1b[m
 
-
-pub struct Gui {
+pub struct Gui<B: Backend = AnsiBackend<Stdout>> {
     width: i64,
     height: i64,
 
     foreground_color_buffer: Vec<Vec<Color>>,
     background_color_buffer: Vec<Vec<Color>>,
-    character_buffer: Vec<Vec<char>>,
-    print_buffer: Vec<char>
-}
+    character_buffer: Vec<Vec<Grapheme>>,
+    style_buffer: Vec<Vec<Style>>,
 
-impl Gui {
-    pub fn new(width: i64, height: i64) -> Self {
-        let width = width as usize; // the reason i64 is used is to limit the size of the gui and to allow the line alg to use negative numbers
-        let height = height as usize;
+    // the surfaces drawn on top of the buffers above, sorted by z when displaying
+    layers: Vec<(LayerId, Surface)>,
+    next_layer_id: u64,
 
-        let mut foreground_color_buffer = Vec::with_capacity(height);
-        let mut background_color_buffer = Vec::with_capacity(height);
-        let mut character_buffer = Vec::with_capacity(height);
+    // the buffers with the layers on top of them, this is what gets compared with the last frame
+    frame: Vec<Vec<Cell>>,
 
-        let mut color_buffer = Vec::with_capacity(width);
-        for _ in 0..width {
-            color_buffer.push(Color::new(0, 0, 0));
-        }
+    // the last frame that was sent to the backend
+    previous_foreground_color_buffer: Vec<Vec<Color>>,
+    previous_background_color_buffer: Vec<Vec<Color>>,
+    previous_character_buffer: Vec<Vec<Grapheme>>,
+    previous_style_buffer: Vec<Vec<Style>>,
 
-        let mut line_buffer = Vec::with_capacity(width);
-        for _ in 0..width {
-            line_buffer.push(' ');
-        }
+    // how colors are combined with the ones already in the buffers when drawing
+    blend_mode: BlendMode,
 
-        for _ in 0..height {
-            foreground_color_buffer.push(color_buffer.clone());
-            background_color_buffer.push(color_buffer.clone());
-            character_buffer.push(line_buffer.clone());
-        }
+    // when set the next call to display sends every cell, not just the changed ones
+    redraw: bool,
 
-        let pixel_count = width * height;
-        let new_line_count = width;
-        let ansi_code_count = 39 * pixel_count + 3;
+    // where the top left corner of the gui is in the terminal, used to turn mouse positions into gui positions
+    origin: (i64, i64),
 
-        let mut print_buffer = Vec::with_capacity(pixel_count + new_line_count + ansi_code_count); // this is a temp size, need to recalculate for the ansi escape codes
+    // while inline the rows are relative to wherever the cursor was, so the terminal row of the top row has to be told
+    inline: bool,
+    inline_row: Option<i64>,
 
-        for _ in 0..print_buffer.capacity() {
-            print_buffer.push(' ');
-        }
+    // the cells that changed since the last frame, kept around to avoid allocating every frame
+    changed_cells: Vec<(i64, i64, Cell)>,
 
-        let mut index = 0;
-        for _ in 0..height {
-            for _ in 0..width { // for every pixel in the screen
-                print_buffer[index] = '\x1b';      index += 1;
-                print_buffer[index] = '[';         index += 1;
-                print_buffer[index] = '3';         index += 1;
-                print_buffer[index] = '8';         index += 1;
-                print_buffer[index] = ';';         index += 1;
-                print_buffer[index] = '2';         index += 1;
-                print_buffer[index] = ';';         index += 4;
-                print_buffer[index] = ';';         index += 4;
-                print_buffer[index] = ';';         index += 4;
-                print_buffer[index] = 'm';         index += 1;
-                print_buffer[index] = '\x1b';      index += 1;
-                print_buffer[index] = '[';         index += 1;
-                print_buffer[index] = '4';         index += 1;
-                print_buffer[index] = '8';         index += 1;
-                print_buffer[index] = ';';         index += 1;
-                print_buffer[index] = '2';         index += 1;
-                print_buffer[index] = ';';         index += 4;
-                print_buffer[index] = ';';         index += 4;
-                print_buffer[index] = ';';         index += 4;
-                print_buffer[index] = 'm';         index += 2;
-            }
+    backend: B
+}
 
-            print_buffer[index] = '\n';   index += 1;
-        }
+impl Gui {
+    /// Creates a gui that displays to stdout
+    pub fn new(width: i64, height: i64) -> Self {
+        Self::with_backend(width, height, AnsiBackend::stdout())
+    }
+
+    /// Creates a gui that fills the terminal and displays to stdout,
+    /// see Term::size_or_default for what size is used when stdout isnt a terminal
+    pub fn fullscreen() -> Self {
+        let (width, height) = Term::size_or_default();
+        Self::new(width, height)
+    }
+}
+
+impl<B: Backend> Gui<B> {
+    /// Creates a gui that displays to the given backend
+    pub fn with_backend(width: i64, height: i64, backend: B) -> Self {
+        let width = width as usize; // the reason i64 is used is to limit the size of the gui and to allow the line alg to use negative numbers
+        let height = height as usize;
+
+        let foreground_color_buffer = vec![vec![Color::TERMINAL_DEFAULT; width]; height];
+        let background_color_buffer = vec![vec![Color::TERMINAL_DEFAULT; width]; height];
+        let character_buffer = vec![vec![Grapheme::from(' '); width]; height];
+        let style_buffer = vec![vec![Style::NONE; width]; height];
 
-        print_buffer[index] = '\x1b'; index += 1;
-        print_buffer[index] = '[';    index += 1;
-        print_buffer[index] = 'm';
+        let previous_foreground_color_buffer = foreground_color_buffer.clone();
+        let previous_background_color_buffer = background_color_buffer.clone();
+        let previous_character_buffer = character_buffer.clone();
+        let previous_style_buffer = style_buffer.clone();
+
+        let frame = vec![vec![Cell::new(' ', Color::TERMINAL_DEFAULT, Color::TERMINAL_DEFAULT); width]; height];
+
+        let changed_cells = Vec::with_capacity(width * height);
 
         let width = width as i64;
         let height = height as i64;
@@ -96,68 +89,198 @@ impl Gui {
             foreground_color_buffer,
             background_color_buffer,
             character_buffer,
-            print_buffer
+            style_buffer,
+            layers: Vec::new(),
+            next_layer_id: 0,
+            frame,
+            previous_foreground_color_buffer,
+            previous_background_color_buffer,
+            previous_character_buffer,
+            previous_style_buffer,
+            blend_mode: BlendMode::Normal,
+            redraw: true,
+            origin: (0, 0),
+            inline: false,
+            inline_row: None,
+            changed_cells,
+            backend
         }
     }
 
-    pub fn display(&mut self) {
-        let mut index = 0;
+    /// Puts the visible layers on top of what was drawn on the gui and sends the cells that changed since the last call to the backend
+    pub fn display(&mut self) -> io::Result<()> {
+        self.composite();
+        self.changed_cells.clear();
 
         for y in 0..self.height as usize {
             for x in 0..self.width as usize { // for every pixel in the screen
-                
-                // Get the character as well as the foreground and background colors
-                let c = self.character_buffer[y][x];
-                let fg = self.foreground_color_buffer[y][x];
-                let bg = self.background_color_buffer[y][x];
-
-                // Get the rgb components of the foreground and background colors
-                let fg_red = format!("{:0>3}", fg.r).chars().collect::<Vec<_>>();
-                let fg_green = format!("{:0>3}", fg.g).chars().collect::<Vec<_>>();
-                let fg_blue = format!("{:0>3}", fg.b).chars().collect::<Vec<_>>();
-
-                let bg_red = format!("{:0>3}", bg.r).chars().collect::<Vec<_>>();
-                let bg_green = format!("{:0>3}", bg.g).chars().collect::<Vec<_>>();
-                let bg_blue = format!("{:0>3}", bg.b).chars().collect::<Vec<_>>();
-
-                // Set the elements in the print buffer to the required characters
-                index += 7;
-                self.print_buffer[index] =   fg_red[0]; index += 1; // fg red
-                self.print_buffer[index] =   fg_red[1]; index += 1;
-                self.print_buffer[index] =   fg_red[2]; index += 2;
-                self.print_buffer[index] = fg_green[0]; index += 1; // fg green
-                self.print_buffer[index] = fg_green[1]; index += 1;
-                self.print_buffer[index] = fg_green[2]; index += 2;
-                self.print_buffer[index] =  fg_blue[0]; index += 1; // fg blue
-                self.print_buffer[index] =  fg_blue[1]; index += 1;
-                self.print_buffer[index] =  fg_blue[2]; index += 9;
-                self.print_buffer[index] =   bg_red[0]; index += 1; // bg red
-                self.print_buffer[index] =   bg_red[1]; index += 1;
-                self.print_buffer[index] =   bg_red[2]; index += 2;
-                self.print_buffer[index] = bg_green[0]; index += 1; // bg green
-                self.print_buffer[index] = bg_green[1]; index += 1;
-                self.print_buffer[index] = bg_green[2]; index += 2;
-                self.print_buffer[index] =  bg_blue[0]; index += 1; // bg blue
-                self.print_buffer[index] =  bg_blue[1]; index += 1;
-                self.print_buffer[index] =  bg_blue[2]; index += 2;
-                self.print_buffer[index] =           c; index += 1; // character
+                let cell = self.frame[y][x];
+
+                let unchanged = cell.c == self.previous_character_buffer[y][x]
+                    && cell.fg == self.previous_foreground_color_buffer[y][x]
+                    && cell.bg == self.previous_background_color_buffer[y][x]
+                    && cell.style == self.previous_style_buffer[y][x];
+
+                if unchanged && !self.redraw {
+                    continue;
+                }
+
+                // continuation cells are printed along with the grapheme before them,
+                // so when only their colors or style change that grapheme has to be sent again
+                if cell.c.is_continuation() && x > 0 {
+                    let lead_sent = self.changed_cells.last()
+                        .is_some_and(|&(lead_x, lead_y, _)| lead_x == x as i64 - 1 && lead_y == y as i64);
+
+                    if !lead_sent {
+                        self.changed_cells.push((x as i64 - 1, y as i64, self.frame[y][x - 1]));
+                    }
+                }
+
+                self.changed_cells.push((x as i64, y as i64, cell));
             }
-            index += 1;
         }
 
-        // turn the print buffer into a string
-        let mut print_string = self.print_buffer.iter().collect::<String>();
+        self.backend.draw(&self.changed_cells)?;
+        self.backend.flush()?;
+
+        // only remember the frame once it made it to the backend, so a failed frame gets sent again
+        for &(x, y, cell) in &self.changed_cells {
+            let x = x as usize;
+            let y = y as usize;
+
+            self.previous_character_buffer[y][x] = cell.c;
+            self.previous_foreground_color_buffer[y][x] = cell.fg;
+            self.previous_background_color_buffer[y][x] = cell.bg;
+            self.previous_style_buffer[y][x] = cell.style;
+        }
+
+        self.redraw = false;
+
+        Ok(())
+    }
 
-        // trim excess spaces, dont know why they are inserted, probably something to do with utf-8
-        print_string = print_string.trim_end_matches(" ").to_string();
+    // builds the frame out of the buffers and the visible layers
+    fn composite(&mut self) {
+        for y in 0..self.height as usize {
+            for x in 0..self.width as usize {
+                self.frame[y][x] = Cell::styled(
+                    self.character_buffer[y][x],
+                    self.foreground_color_buffer[y][x],
+                    self.background_color_buffer[y][x],
+                    self.style_buffer[y][x]
+                );
+            }
+        }
+
+        // ids go up as layers are added, so layers with the same z stay in the order they were added even after their z changed
+        self.layers.sort_by_key(|(id, surface)| (surface.z(), id.0));
+
+        for (_, surface) in &self.layers {
+            if surface.visible() {
+                surface.composite(&mut self.frame);
+            }
+        }
+    }
+
+    /// Adds a surface that gets drawn on top of the gui every time it is displayed
+    pub fn add_layer(&mut self, surface: Surface) -> LayerId {
+        let id = LayerId(self.next_layer_id);
+        self.next_layer_id += 1;
+
+        self.layers.push((id, surface));
+        id
+    }
+
+    /// Removes a layer and gives back its surface, None if there is no layer with the id
+    pub fn remove_layer(&mut self, id: LayerId) -> Option<Surface> {
+        let index = self.layers.iter().position(|(layer_id, _)| *layer_id == id)?;
+        Some(self.layers.remove(index).1)
+    }
+
+    pub fn layer(&self, id: LayerId) -> Option<&Surface> {
+        self.layers.iter().find(|(layer_id, _)| *layer_id == id).map(|(_, surface)| surface)
+    }
+
+    pub fn layer_mut(&mut self, id: LayerId) -> Option<&mut Surface> {
+        self.layers.iter_mut().find(|(layer_id, _)| *layer_id == id).map(|(_, surface)| surface)
+    }
+
+    /// Changes the size of the gui, keeping what was drawn where it still fits,
+    /// new cells start out blank and everything gets sent again on the next display
+    ///
+    /// The terminal may have moved things around when it was resized, so clearing it first is a good idea
+    pub fn resize(&mut self, width: i64, height: i64) {
+        let width = width.max(0) as usize;
+        let height = height.max(0) as usize;
+
+        resize_buffer(&mut self.foreground_color_buffer, width, height, Color::TERMINAL_DEFAULT);
+        resize_buffer(&mut self.background_color_buffer, width, height, Color::TERMINAL_DEFAULT);
+        resize_buffer(&mut self.character_buffer, width, height, Grapheme::from(' '));
+        resize_buffer(&mut self.style_buffer, width, height, Style::NONE);
+        resize_buffer(&mut self.frame, width, height, Cell::new(' ', Color::TERMINAL_DEFAULT, Color::TERMINAL_DEFAULT));
+
+        resize_buffer(&mut self.previous_foreground_color_buffer, width, height, Color::TERMINAL_DEFAULT);
+        resize_buffer(&mut self.previous_background_color_buffer, width, height, Color::TERMINAL_DEFAULT);
+        resize_buffer(&mut self.previous_character_buffer, width, height, Grapheme::from(' '));
+        resize_buffer(&mut self.previous_style_buffer, width, height, Style::NONE);
+
+        // a wide grapheme in the last column lost its continuation cell, so it wouldnt fit anymore
+        for row in self.character_buffer.iter_mut() {
+            if let Some(last) = row.last_mut() {
+                if last.width() == 2 {
+                    *last = Grapheme::from(' ');
+                }
+            }
+        }
 
-        // print the string to stdout
-        print!("\x1b[H{}", print_string);
-        std::io::stdout().flush().unwrap();
+        self.width = width as i64;
+        self.height = height as i64;
+        self.redraw = true;
+    }
+
+    /// Makes the next call to display send every cell,
+    /// use this if something else has drawn over the gui
+    pub fn redraw(&mut self) {
+        self.redraw = true;
+    }
+
+    /// Sets how many colors the backend can show and redraws everything with the new colors
+    pub fn set_color_depth(&mut self, depth: ColorDepth) {
+        self.backend.set_color_depth(depth);
+        self.redraw = true;
+    }
+
+    /// Sets how the colors of everything drawn from now on are combined with the colors already there
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.blend_mode = mode;
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
     }
 }
 
-impl Gui {
+// resizes the rows and columns of a buffer in place, filling new cells with the value
+fn resize_buffer<T: Clone>(buffer: &mut Vec<Vec<T>>, width: usize, height: usize, value: T) {
+    buffer.resize(height, Vec::new());
+
+    for row in buffer.iter_mut() {
+        row.resize(width, value.clone());
+    }
+}
+
+// the size and the drawing functions gui had before Canvas, so code that doesnt import Canvas keeps working,
+// they do the same thing as the Canvas functions with the same name
+#[allow(clippy::too_many_arguments)]
+impl<B: Backend> Gui<B> {
     pub fn width(&self) -> &i64 {
         &self.width
     }
@@ -165,26 +288,167 @@ impl Gui {
     pub fn height(&self) -> &i64 {
         &self.height
     }
+
+    pub fn pixel(&mut self, x: i64, y: i64, c: char, fg: Color, bg: Color) {
+        Canvas::pixel(self, x, y, c, fg, bg);
+    }
+
+    pub fn clear(&mut self, c: char, fg: Color, bg: Color) {
+        Canvas::clear(self, c, fg, bg);
+    }
+
+    pub fn stroke_rect(&mut self, x: i64, y: i64, width: i64, height: i64, c: char, fg: Color, bg: Color) {
+        Canvas::stroke_rect(self, x, y, width, height, c, fg, bg);
+    }
+
+    pub fn fill_rect(&mut self, x: i64, y: i64, width: i64, height: i64, c: char, fg: Color, bg: Color) {
+        Canvas::fill_rect(self, x, y, width, height, c, fg, bg);
+    }
+
+    pub fn rect(&mut self, x: i64, y: i64, width: i64, height: i64, stroke_char: char, fill_char: char, stroke_fg: Color, stroke_bg: Color, fill_fg: Color, fill_bg: Color) {
+        Canvas::rect(self, x, y, width, height, stroke_char, fill_char, stroke_fg, stroke_bg, fill_fg, fill_bg);
+    }
+
+    pub fn horizontal_text(&mut self, x: i64, y: i64, text: &str, fg: Color, bg: Color) {
+        Canvas::horizontal_text(self, x, y, text, fg, bg);
+    }
+
+    pub fn vertical_text(&mut self, x: i64, y: i64, text: &str, fg: Color, bg: Color) {
+        Canvas::vertical_text(self, x, y, text, fg, bg);
+    }
+
+    pub fn line(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, c: char, fg: Color, bg: Color) {
+        Canvas::line(self, x0, y0, x1, y1, c, fg, bg);
+    }
 }
 
-impl Gui { // Helpers
-    pub fn clear_terminal(&self) {
-        print!("\x1b[H\x1b[2J");
+impl<B: Backend> Gui<B> { // Helpers
+    pub fn clear_terminal(&mut self) -> io::Result<()> {
+        self.redraw = true;
+        self.backend.clear()?;
+        self.backend.flush()
+    }
+
+    /// Switches to the alternate screen, the contents of the normal screen come back when leaving it
+    pub fn enter_alternate_screen(&mut self) -> io::Result<()> {
+        self.redraw = true;
+        self.backend.enter_alternate_screen()?;
+        self.backend.flush()
+    }
+
+    pub fn leave_alternate_screen(&mut self) -> io::Result<()> {
+        self.redraw = true;
+        self.backend.leave_alternate_screen()?;
+        self.backend.flush()
+    }
+
+    /// Limits scrolling in the terminal to the rows from top to bottom (inclusive, starting at 0),
+    /// the cursor is moved to the bottom row of the region
+    pub fn set_scroll_region(&mut self, top: i64, bottom: i64) -> io::Result<()> {
+        self.backend.set_scroll_region(top, bottom)?;
+        self.backend.flush()
+    }
+
+    /// Lets the whole terminal scroll again
+    pub fn reset_scroll_region(&mut self) -> io::Result<()> {
+        self.backend.reset_scroll_region()?;
+        self.backend.flush()
+    }
+
+    /// Sets where the top left corner of the gui is drawn in the terminal
+    pub fn set_origin(&mut self, x: i64, y: i64) {
+        self.backend.set_origin(x, y);
+        self.origin = (x, y);
+        self.redraw = true;
+    }
+
+    /// Moves the gui to the bottom rows of a terminal with the given height and limits scrolling to the rows above it,
+    /// so normal output keeps scrolling above the gui while it is displayed
+    pub fn dock_bottom(&mut self, terminal_height: i64) -> io::Result<()> {
+        let top = (terminal_height - self.height).max(0);
+
+        // make room for the gui so it doesnt draw over whatever is at the bottom of the terminal
+        self.backend.scroll_up(self.height)?;
+
+        self.set_origin(0, top);
+
+        if top > 0 {
+            self.set_scroll_region(0, top - 1)?;
+        }
+
+        Ok(())
+    }
+
+    /// Undoes dock_bottom, letting the whole terminal scroll again and drawing the gui at the top left corner
+    pub fn undock(&mut self) -> io::Result<()> {
+        self.set_origin(0, 0);
+        self.reset_scroll_region()
+    }
+
+    /// Makes the gui draw at the line the cursor is on instead of at its origin, so it can show up
+    /// in the middle of normal output, later frames draw over the same rows
+    ///
+    /// Nothing else should be printed while the gui is inline, since that moves the rows it is drawn in
+    pub fn enter_inline(&mut self) -> io::Result<()> {
+        self.redraw = true;
+        self.inline = true;
+        self.inline_row = None;
+        self.backend.enter_inline(self.height)?;
+        self.backend.flush()
     }
 
-    pub fn hide_cursor(&self) {
-        print!("\x1b[?25l");
-        std::io::stdout().flush().unwrap();
+    /// Tells an inline gui which row of the terminal its top row is on, so mouse_event can find its cells,
+    /// Term::cursor_position right after enter_inline gives the row
+    pub fn set_inline_row(&mut self, row: i64) {
+        self.inline_row = Some(row);
     }
 
-    pub fn show_cursor(&self) {
-        print!("\x1b[?25h");
-        std::io::stdout().flush().unwrap();
+    /// Stops drawing inline and moves the cursor to the line below the gui so normal output continues after it
+    pub fn leave_inline(&mut self) -> io::Result<()> {
+        self.inline = false;
+        self.inline_row = None;
+        self.backend.leave_inline()?;
+        self.backend.flush()
+    }
+
+    /// Moves the position of a mouse event from the terminal to the gui, taking the origin and inline row into account,
+    /// None if it happened outside of the gui or if the gui is inline and doesnt know its row
+    pub fn mouse_event(&self, event: MouseEvent) -> Option<MouseEvent> {
+        let top = if self.inline {self.inline_row?} else {self.origin.1};
+
+        let x = event.x - self.origin.0;
+        let y = event.y - top;
+
+        if x >= self.width || y >= self.height || x < 0 || y < 0 {
+            return None;
+        }
+
+        Some(MouseEvent {x, y, ..event})
+    }
+
+    pub fn hide_cursor(&mut self) -> io::Result<()> {
+        self.backend.hide_cursor()?;
+        self.backend.flush()
+    }
+
+    pub fn show_cursor(&mut self) -> io::Result<()> {
+        self.backend.show_cursor()?;
+        self.backend.flush()
     }
 }
 
-impl Gui { // Drawing Functions
-    pub fn pixel(&mut self, x: i64, y: i64, c: char, fg: Color, bg: Color) {
+
+
+impl<B: Backend> Canvas for Gui<B> {
+    fn width(&self) -> i64 {
+        self.width
+    }
+
+    fn height(&self) -> i64 {
+        self.height
+    }
+
+    fn draw_cell(&mut self, x: i64, y: i64, cell: Cell) {
         if x >= self.width || y >= self.height || x < 0 || y < 0 {
             return;
         }
@@ -192,194 +456,294 @@ impl Gui { // Drawing Functions
         let x = x as usize;
         let y = y as usize;
 
-        self.character_buffer[y][x] = c;
+        self.character_buffer[y][x] = cell.c;
+        self.style_buffer[y][x] = cell.style;
 
-        if fg != Color::DEFAULT {
-            self.foreground_color_buffer[y][x] = fg;
-        }
-    
-        if bg != Color::DEFAULT {
-            self.background_color_buffer[y][x] = bg;
-        }
+        // transparent colors leave the buffers alone and colors with alpha are mixed with what is there
+        self.foreground_color_buffer[y][x] = cell.fg.blend(self.foreground_color_buffer[y][x], self.blend_mode);
+        self.background_color_buffer[y][x] = cell.bg.blend(self.background_color_buffer[y][x], self.blend_mode);
     }
 
-    pub fn clear(&mut self, c: char, fg: Color, bg: Color) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                self.pixel(x, y, c, fg, bg);
-            }
+    fn cell(&self, x: i64, y: i64) -> Option<Cell> {
+        if x >= self.width || y >= self.height || x < 0 || y < 0 {
+            return None;
         }
+
+        let x = x as usize;
+        let y = y as usize;
+
+        Some(Cell::styled(self.character_buffer[y][x], self.foreground_color_buffer[y][x], self.background_color_buffer[y][x], self.style_buffer[y][x]))
     }
+}
 
-    pub fn stroke_rect(&mut self, x: i64, y: i64, width: i64, height: i64, c: char, fg: Color, bg: Color) {
-        for x in x..x+width {
-            self.pixel(x, y, c, fg, bg);
-            self.pixel(x, y+height-1, c, fg, bg);
-        }
 
-        for y in y..y+height {
-            self.pixel(x, y, c, fg, bg);
-            self.pixel(x+width-1, y-1, c, fg, bg);
-        }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::test_backend::test_gui::{dotted_gui, text};
+    use crate::graphics::{TestBackend, VtScreen};
+    use crate::io::{Modifiers, MouseButton, MouseKind};
+
+    fn ansi_gui(width: i64, height: i64) -> Gui<AnsiBackend<Vec<u8>>> {
+        Gui::with_backend(width, height, AnsiBackend::new(Vec::new()))
     }
 
-    pub fn fill_rect(&mut self, x: i64, y: i64, width: i64, height: i64, c: char, fg: Color, bg: Color) {
-        for y in y..y+height {
-            for x in x..x+width {
-                self.pixel(x, y, c, fg, bg);
-            }
-        }
+    // displays the gui and returns what was written to the backend
+    fn display_output(gui: &mut Gui<AnsiBackend<Vec<u8>>>) -> String {
+        gui.backend_mut().get_mut().clear();
+        gui.display().unwrap();
+        String::from_utf8(gui.backend().get_ref().clone()).unwrap()
     }
 
-    pub fn rect(&mut self, x: i64, y: i64, width: i64, height: i64, stroke_char: char, fill_char: char, stroke_fg: Color, stroke_bg: Color, fill_fg: Color, fill_bg: Color) {
-        for y_coord in 0..height {
-            for x_coord in 0..width {
-                if x_coord == 0 || x_coord == width - 1 || y_coord == 0 || y_coord == height - 1 {
-                    self.pixel(x + x_coord, y + y_coord, stroke_char, stroke_fg, stroke_bg);
-                }
-                else {
-                    self.pixel(x + x_coord, y + y_coord, fill_char, fill_fg, fill_bg);
-                }
-            }
-        }
+    #[test]
+    fn first_display_sends_every_cell() {
+        let mut gui = Gui::with_backend(3, 2, TestBackend::new(3, 2));
+        gui.pixel(1, 1, 'x', Color::WHITE, Color::BLACK);
+        gui.display().unwrap();
+
+        gui.backend().assert_text("   \n x ");
+        gui.backend().assert_cell(1, 1, Cell::new('x', Color::WHITE, Color::BLACK));
+        assert_eq!(gui.backend().frame_count(), 1);
     }
 
-    pub fn horizontal_text(&mut self, x: i64, y: i64, text: &str, fg: Color, bg: Color) {
-        for i in 0..text.len() as i64 {
-            self.pixel(x + i, y, text.chars().collect::<Vec<_>>()[i as usize], fg, bg);
-        }
+    #[test]
+    fn unchanged_frame_sends_nothing() {
+        let mut gui = ansi_gui(4, 3);
+        gui.fill_rect(0, 0, 4, 3, '#', Color::WHITE, Color::BLACK);
+        display_output(&mut gui);
+
+        gui.fill_rect(0, 0, 4, 3, '#', Color::WHITE, Color::BLACK);
+        assert_eq!(display_output(&mut gui), "");
     }
 
-    pub fn vertical_text(&mut self, x: i64, y: i64, text: &str, fg: Color, bg: Color) {
-        for i in 0..text.len() as i64 {
-            self.pixel(x, y + i, text.chars().collect::<Vec<_>>()[i as usize], fg, bg);
-        }
+    #[test]
+    fn only_changed_cells_are_sent() {
+        let mut gui = ansi_gui(8, 4);
+        gui.clear('.', Color::WHITE, Color::BLACK);
+        display_output(&mut gui);
+
+        gui.pixel(3, 2, 'a', Color::WHITE, Color::BLACK);
+        gui.pixel(6, 0, 'b', Color::WHITE, Color::BLACK);
+
+        // each cell gets a cursor jump, the colors are only sent once since they are the same
+        assert_eq!(display_output(&mut gui), "\x1b[1;7H\x1b[38;2;255;255;255;48;2;0;0;0mb\x1b[3;4Ha\x1b[m");
     }
 
-    pub fn line(&mut self, mut x0: i64, mut y0: i64, mut x1: i64, mut y1: i64, c: char, fg: Color, bg: Color) { // bresenham's line alg
-        let mut dx = x1 - x0;
-        let mut dy = y1 - y0;
+    #[test]
+    fn neighbouring_changes_share_a_cursor_jump() {
+        let mut gui = ansi_gui(8, 4);
+        gui.clear('.', Color::WHITE, Color::BLACK);
+        display_output(&mut gui);
 
-        if dy == 0 { // Horizontal
-            if x0 > x1 {
-                let temp = x0;
-                x0 = x1;
-                x1 = temp;
-                y0 = y1;
-            }
+        gui.horizontal_text(2, 1, "hi", Color::WHITE, Color::BLACK);
 
-            for x in x0..=x1 {
-                self.pixel(x, y0, c, fg, bg);
-            }
+        assert_eq!(display_output(&mut gui), "\x1b[2;3H\x1b[38;2;255;255;255;48;2;0;0;0mhi\x1b[m");
+    }
 
-            return;
-        }
-        else if dx == 0 { // Vertical
-            if y0 > y1 {
-                x0 = x1;
-                let temp = y0;
-                y0 = y1;
-                y1 = temp;
-            }
+    #[test]
+    fn shrinking_through_a_wide_grapheme_replaces_it() {
+        let mut gui = Gui::with_backend(4, 1, AnsiBackend::new(VtScreen::new(4, 1)));
+        gui.horizontal_text(0, 0, "ab日", Color::WHITE, Color::BLACK);
+        gui.display().unwrap();
 
-            for y in y0..=y1 {
-                self.pixel(x0, y, c, fg, bg);
-            }
+        gui.resize(3, 1);
+        gui.display().unwrap();
 
-            return;
-        }
-        
-        if x0 > x1 { // move everything to the 4 right octants (dx is always positive)
-            let mut temp = x0;
-            x0 = x1;
-            x1 = temp;
-            temp = y0;
-            y0 = y1;
-            y1 = temp;
-
-            dx = x1 - x0;
-            dy = y1 - y0;
-        }
+        assert_eq!(Canvas::cell(&gui, 2, 0).unwrap().c, ' ');
 
-        let loop_x = dy.abs() <= dx.abs();
+        // the lead alone would have been written past the last column
+        assert_eq!(gui.backend().get_ref().text(), "ab ");
+    }
 
-        if loop_x {
-            if x0 > x1 {
-                let mut temp = x0;
-                x0 = x1;
-                x1 = temp;
-                temp = y0;
-                y0 = y1;
-                y1 = temp;
+    #[test]
+    fn resize_keeps_what_fits_and_sends_everything() {
+        let mut gui = Gui::with_backend(3, 2, TestBackend::new(4, 3));
+        gui.clear('.', Color::WHITE, Color::BLACK);
+        gui.pixel(1, 1, 'x', Color::WHITE, Color::BLACK);
+        gui.display().unwrap();
 
-                dx = x1 - x0;
-                dy = y1 - y0;
-            }
+        gui.resize(4, 3);
+        assert_eq!((*gui.width(), *gui.height()), (4, 3));
 
-            let res_mod = if dy > 0 {1} else {-1};
-            let mut y = y0;
-            let mut err = -dx;
-            let slope = dy;
-            let dx2 = dx << 1; // bitshift instead of multiply by 2
+        gui.display().unwrap();
+        gui.backend().assert_text("... \n.x. \n    ");
 
-            for x in x0..=x1 {
-                self.pixel(x, y, c, fg, bg);
-                err += 2 * slope.abs();
+        // cells that didnt change are sent again too, the terminal may have moved them when it was resized
+        gui.resize(4, 3);
+        gui.display().unwrap();
+        assert_eq!(gui.backend().frame_count(), 3);
+    }
 
-                if err >= 0 {
-                    err -= dx2;
-                    y += res_mod;
-                }
-            }
-        }
-        else {
-            if y0 > y1 {
-                let mut temp = x0;
-                x0 = x1;
-                x1 = temp;
-                temp = y0;
-                y0 = y1;
-                y1 = temp;
-
-                dx = x1 - x0;
-                dy = y1 - y0;
-            }
+    // a surface filled with the character
+    fn filled_surface(width: i64, height: i64, c: char) -> Surface {
+        let mut surface = Surface::new(width, height);
+        surface.fill_rect(0, 0, width, height, c, Color::WHITE, Color::BLACK);
+        surface
+    }
 
-            let res_mod = if dx > 0 {1} else {-1};
-            let mut x = x0;
-            let mut err = -dy;
-            let slope = dx;
-            let dy2 = dy << 1; // bitshift instead of multiply by 2
+    #[test]
+    fn layers_are_drawn_in_z_order() {
+        let mut gui = dotted_gui(3, 1);
 
-            for y in y0..=y1 {
-                self.pixel(x, y, c, fg, bg);
-                err += 2 * slope.abs();
+        let mut top = filled_surface(2, 1, 'a');
+        top.set_z(1);
+        let top = gui.add_layer(top);
+        gui.add_layer(filled_surface(3, 1, 'b'));
 
-                if err >= 0 {
-                    err -= dy2;
-                    x += res_mod;
-                }
-            }
+        assert_eq!(text(&mut gui), "aab");
+
+        gui.layer_mut(top).unwrap().set_z(-1);
+        assert_eq!(text(&mut gui), "bbb");
+    }
+
+    #[test]
+    fn layers_with_the_same_z_keep_the_order_they_were_added() {
+        let mut gui = dotted_gui(3, 1);
+        let first = gui.add_layer(filled_surface(3, 1, 'a'));
+        gui.add_layer(filled_surface(2, 1, 'b'));
+
+        assert_eq!(text(&mut gui), "bba");
+
+        // moving a layer away and back doesnt change where it goes among the others
+        gui.layer_mut(first).unwrap().set_z(5);
+        assert_eq!(text(&mut gui), "aaa");
+
+        gui.layer_mut(first).unwrap().set_z(0);
+        assert_eq!(text(&mut gui), "bba");
+    }
+
+    #[test]
+    fn hidden_layers_arent_drawn() {
+        let mut gui = dotted_gui(3, 1);
+        let layer = gui.add_layer(filled_surface(3, 1, 'a'));
+
+        gui.layer_mut(layer).unwrap().set_visible(false);
+        assert_eq!(text(&mut gui), "...");
+
+        gui.layer_mut(layer).unwrap().set_visible(true);
+        assert_eq!(text(&mut gui), "aaa");
+    }
+
+    #[test]
+    fn layers_are_cut_off_at_the_edges_of_the_gui() {
+        let mut gui = dotted_gui(3, 3);
+        let layer = gui.add_layer(filled_surface(2, 2, 'a'));
+
+        gui.layer_mut(layer).unwrap().set_offset(-1, -1);
+        assert_eq!(text(&mut gui), "a..\n...\n...");
+
+        gui.layer_mut(layer).unwrap().set_offset(2, 1);
+        assert_eq!(text(&mut gui), "...\n..a\n..a");
+
+        for (x, y) in [(3, 0), (0, 3), (-2, 0), (0, -2), (i64::MAX / 2, i64::MIN / 2)] {
+            gui.layer_mut(layer).unwrap().set_offset(x, y);
+            assert_eq!(text(&mut gui), "...\n...\n...", "offset ({}, {})", x, y);
         }
-        
-   
     }
-}
 
-// let dx = x1 - x0;
-// let dy = y1 - y0;
+    #[test]
+    fn removed_layers_arent_drawn() {
+        let mut gui = dotted_gui(3, 1);
+        let layer = gui.add_layer(filled_surface(3, 1, 'a'));
+        assert_eq!(text(&mut gui), "aaa");
 
-// let mut y = y0;
-// let slope = 2 * dy;
-// let mut err = -dx;
-// let err_inc = -2 * dx;
+        let surface = gui.remove_layer(layer).unwrap();
+        assert_eq!(surface.cell(0, 0).unwrap().c, 'a');
+        assert_eq!(text(&mut gui), "...");
 
-// for x in x0..=x1 {
-//     self.pixel(x, y, c, fg, bg);
-//     err += slope;
+        assert!(gui.layer(layer).is_none());
+        assert!(gui.remove_layer(layer).is_none());
+    }
+
+    #[test]
+    fn recolored_continuation_resends_its_lead() {
+        let mut gui = ansi_gui(4, 1);
+        gui.horizontal_text(0, 0, "日", Color::WHITE, Color::BLACK);
+        display_output(&mut gui);
+
+        // the right half of the grapheme is the only cell that changed
+        Canvas::draw_cell(&mut gui, 1, 0, Cell::styled(Grapheme::CONTINUATION, Color::RED, Color::BLACK, Style::NONE));
+
+        assert!(display_output(&mut gui).contains('日'));
+    }
+
+    #[test]
+    fn diff_frames_rebuild_the_screen() {
+        let mut gui = Gui::with_backend(8, 4, AnsiBackend::new(VtScreen::new(8, 4)));
+        gui.clear('.', Color::WHITE, Color::BLACK);
+        gui.display().unwrap();
+
+        gui.pixel(3, 2, 'a', Color::new(255, 0, 0), Color::BLACK);
+        gui.pixel(6, 0, 'b', Color::WHITE, Color::new(0, 0, 255));
+        gui.display().unwrap();
+
+        let screen = gui.backend().get_ref();
+        assert_eq!(screen.text(), "......b.\n........\n...a....\n........");
+        assert_eq!(*screen.cell(3, 2).unwrap(), Cell::new('a', Color::new(255, 0, 0), Color::BLACK));
+        assert_eq!(*screen.cell(6, 0).unwrap(), Cell::new('b', Color::WHITE, Color::new(0, 0, 255)));
+    }
+
+    fn click(x: i64, y: i64) -> MouseEvent {
+        MouseEvent {kind: MouseKind::Down(MouseButton::Left), x, y, modifiers: Modifiers::NONE}
+    }
+
+    #[test]
+    fn mouse_events_at_the_top_left_corner_stay_put() {
+        let gui = Gui::with_backend(10, 5, TestBackend::new(10, 5));
+
+        assert_eq!(gui.mouse_event(click(3, 4)), Some(click(3, 4)));
+        assert_eq!(gui.mouse_event(click(10, 0)), None);
+        assert_eq!(gui.mouse_event(click(0, 5)), None);
+    }
+
+    #[test]
+    fn mouse_events_are_moved_by_the_origin() {
+        let mut gui = Gui::with_backend(10, 5, TestBackend::new(10, 5));
+        gui.set_origin(4, 2);
 
-//     if err >= 0 {
-//         err += err_inc;
-//         y += 1;
-//     }
-// }
\ No newline at end of file
+        assert_eq!(gui.mouse_event(click(4, 2)), Some(click(0, 0)));
+        assert_eq!(gui.mouse_event(click(13, 6)), Some(click(9, 4)));
+        assert_eq!(gui.mouse_event(click(3, 2)), None);
+        assert_eq!(gui.mouse_event(click(14, 6)), None);
+    }
+
+    #[test]
+    fn mouse_events_on_a_docked_gui() {
+        let mut gui = Gui::with_backend(10, 5, TestBackend::new(10, 5));
+        gui.dock_bottom(24).unwrap();
+
+        assert_eq!(gui.mouse_event(click(2, 19)), Some(click(2, 0)));
+        assert_eq!(gui.mouse_event(click(2, 23)), Some(click(2, 4)));
+        assert_eq!(gui.mouse_event(click(2, 18)), None);
+
+        gui.undock().unwrap();
+        assert_eq!(gui.mouse_event(click(2, 3)), Some(click(2, 3)));
+    }
+
+    #[test]
+    fn mouse_events_on_an_inline_gui() {
+        let mut gui = Gui::with_backend(10, 5, TestBackend::new(10, 5));
+        gui.enter_inline().unwrap();
+
+        // the gui cant tell where it is until it is told its row
+        assert_eq!(gui.mouse_event(click(2, 0)), None);
+
+        gui.set_inline_row(12);
+        assert_eq!(gui.mouse_event(click(2, 12)), Some(click(2, 0)));
+        assert_eq!(gui.mouse_event(click(2, 16)), Some(click(2, 4)));
+        assert_eq!(gui.mouse_event(click(2, 11)), None);
+
+        gui.leave_inline().unwrap();
+        assert_eq!(gui.mouse_event(click(2, 3)), Some(click(2, 3)));
+    }
+
+    #[test]
+    fn redraw_sends_every_cell_again() {
+        let mut gui = ansi_gui(2, 1);
+        display_output(&mut gui);
+
+        gui.redraw();
+        assert_eq!(display_output(&mut gui), "\x1b[1;1H\x1b[39;49m  \x1b[m");
+    }
+}
@@ -1,10 +1,29 @@
+mod backend;
+mod border;
+mod canvas;
+mod cell;
 mod color;
+mod grapheme;
 mod gui;
+mod style;
+mod surface;
+mod test_backend;
+mod view;
+mod vt;
 
+pub use backend::*;
+pub use border::*;
+pub use canvas::*;
+pub use cell::*;
 pub use color::*;
+pub use grapheme::*;
 pub use gui::*;
+pub use style::*;
+pub use surface::*;
+pub use test_backend::*;
+pub use view::*;
+pub use vt::*;
 
 pub mod prelude {
     pub use crate::graphics::*;
-    pub use crate::graphics::*;
-}
\ No newline at end of file
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+use std::ops::{BitOr, BitOrAssign, Sub, SubAssign};
+
+/// The text attributes of a cell, combine them with | (Style::BOLD | Style::REVERSE) and take them away with -
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Style {
+    bits: u8
+}
+
+// every attribute along with the sgr code that turns it on, in the order the codes are sent
+const ATTRIBUTES: [(Style, u8, &str); 7] = [
+    (Style::BOLD, 1, "BOLD"),
+    (Style::DIM, 2, "DIM"),
+    (Style::ITALIC, 3, "ITALIC"),
+    (Style::UNDERLINE, 4, "UNDERLINE"),
+    (Style::BLINK, 5, "BLINK"),
+    (Style::REVERSE, 7, "REVERSE"),
+    (Style::STRIKETHROUGH, 9, "STRIKETHROUGH")
+];
+
+impl Style {
+    pub const NONE: Style = Style {bits: 0};
+    pub const BOLD: Style = Style {bits: 1};
+    pub const DIM: Style = Style {bits: 1 << 1};
+    pub const ITALIC: Style = Style {bits: 1 << 2};
+    pub const UNDERLINE: Style = Style {bits: 1 << 3};
+    pub const BLINK: Style = Style {bits: 1 << 4};
+    /// Swaps the foreground and background colors
+    pub const REVERSE: Style = Style {bits: 1 << 5};
+    pub const STRIKETHROUGH: Style = Style {bits: 1 << 6};
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// True if every attribute in other is also in this style
+    pub fn contains(&self, other: Style) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    pub fn insert(&mut self, other: Style) {
+        self.bits |= other.bits;
+    }
+
+    pub fn remove(&mut self, other: Style) {
+        self.bits &= !other.bits;
+    }
+
+    /// The sgr codes that turn on the attributes of this style
+    pub fn sgr_codes(&self) -> impl Iterator<Item = u8> + '_ {
+        ATTRIBUTES.iter()
+            .filter(|(attribute, _, _)| self.contains(*attribute))
+            .map(|&(_, code, _)| code)
+    }
+
+    /// Applies a single sgr code to the style, codes that arent text attributes are ignored
+    pub fn apply_sgr(&mut self, code: u16) {
+        match code {
+            0 => *self = Style::NONE,
+            21 | 22 => self.remove(Style::BOLD | Style::DIM),
+            23 => self.remove(Style::ITALIC),
+            24 => self.remove(Style::UNDERLINE),
+            25 => self.remove(Style::BLINK),
+            27 => self.remove(Style::REVERSE),
+            29 => self.remove(Style::STRIKETHROUGH),
+            code => {
+                if let Some(&(attribute, _, _)) = ATTRIBUTES.iter().find(|&&(_, attribute_code, _)| attribute_code as u16 == code) {
+                    self.insert(attribute);
+                }
+            }
+        }
+    }
+}
+
+// prints the names of the attributes, like Style(BOLD | REVERSE)
+impl fmt::Debug for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = ATTRIBUTES.iter()
+            .filter(|(attribute, _, _)| self.contains(*attribute))
+            .map(|&(_, _, name)| name)
+            .collect();
+
+        if names.is_empty() {
+            write!(f, "Style(NONE)")
+        }
+        else {
+            write!(f, "Style({})", names.join(" | "))
+        }
+    }
+}
+
+impl BitOr for Style {
+    type Output = Style;
+
+    fn bitor(self, other: Style) -> Style {
+        Style {bits: self.bits | other.bits}
+    }
+}
+
+impl BitOrAssign for Style {
+    fn bitor_assign(&mut self, other: Style) {
+        self.insert(other);
+    }
+}
+
+impl Sub for Style {
+    type Output = Style;
+
+    fn sub(self, other: Style) -> Style {
+        Style {bits: self.bits & !other.bits}
+    }
+}
+
+impl SubAssign for Style {
+    fn sub_assign(&mut self, other: Style) {
+        self.remove(other);
+    }
+}
@@ -0,0 +1,243 @@
+use super::{BlendMode, Canvas, Cell, Color, Grapheme, Style};
+
+/// Identifies a layer that was added to a gui
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct LayerId(pub(super) u64);
+
+// cells that were never drawn on, they dont change anything below them
+const EMPTY: Cell = Cell {c: Grapheme::NONE, fg: Color::Transparent, bg: Color::Transparent, style: Style::NONE};
+
+/// An off screen canvas that is drawn on top of a gui once it is added to it as a layer,
+/// cells that were never drawn on are see through and so are transparent colors
+///
+/// Layers with a higher z are drawn on top, layers with the same z are drawn in the order they were added
+pub struct Surface {
+    width: i64,
+    height: i64,
+
+    cells: Vec<Vec<Cell>>,
+
+    z: i64,
+    visible: bool,
+    offset: (i64, i64),
+
+    // how the surface is combined with the layers below it
+    blend_mode: BlendMode
+}
+
+impl Surface {
+    pub fn new(width: i64, height: i64) -> Self {
+        let width = width.max(0);
+        let height = height.max(0);
+
+        Self {
+            width,
+            height,
+            cells: vec![vec![EMPTY; width as usize]; height as usize],
+            z: 0,
+            visible: true,
+            offset: (0, 0),
+            blend_mode: BlendMode::Normal
+        }
+    }
+
+    pub fn z(&self) -> i64 {
+        self.z
+    }
+
+    pub fn set_z(&mut self, z: i64) {
+        self.z = z;
+    }
+
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Hidden surfaces keep what was drawn on them but arent drawn on the gui
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn offset(&self) -> (i64, i64) {
+        self.offset
+    }
+
+    /// Sets where the top left corner of the surface is drawn on the gui
+    pub fn set_offset(&mut self, x: i64, y: i64) {
+        self.offset = (x, y);
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Sets how the colors of the surface are combined with the layers below it,
+    /// drawing on the surface itself always uses the normal blend mode
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.blend_mode = mode;
+    }
+
+    /// Makes every cell see through again, as if nothing was ever drawn on the surface
+    pub fn erase(&mut self) {
+        for row in self.cells.iter_mut() {
+            row.fill(EMPTY);
+        }
+    }
+
+    // draws the surface on top of the frame, the frame is in gui coordinates
+    pub(super) fn composite(&self, frame: &mut [Vec<Cell>]) {
+        let (offset_x, offset_y) = self.offset;
+
+        for (y, row) in self.cells.iter().enumerate() {
+            let frame_row = match usize::try_from(y as i64 + offset_y).ok().and_then(|y| frame.get_mut(y)) {
+                Some(frame_row) => frame_row,
+                None => continue
+            };
+
+            for (x, cell) in row.iter().enumerate() {
+                let frame_x = match usize::try_from(x as i64 + offset_x) {
+                    Ok(frame_x) if frame_x < frame_row.len() => frame_x,
+                    _ => continue
+                };
+
+                if !cell.c.is_empty() {
+                    // dont leave half of a wide grapheme of the layers below behind
+                    let replaced = frame_row[frame_x].c;
+
+                    if replaced.is_continuation() && frame_x > 0 {
+                        frame_row[frame_x - 1].c = Grapheme::from(' ');
+                    }
+                    else if replaced.width() == 2 && frame_x + 1 < frame_row.len() {
+                        frame_row[frame_x + 1].c = Grapheme::from(' ');
+                    }
+
+                    // nor half of one of this surface where the edge of the gui cuts it off
+                    let cut_off = (cell.c.width() == 2 && frame_x + 1 == frame_row.len()) || (cell.c.is_continuation() && frame_x == 0);
+
+                    frame_row[frame_x].c = if cut_off {Grapheme::from(' ')} else {cell.c};
+                    frame_row[frame_x].style = cell.style;
+                }
+
+                let below = &mut frame_row[frame_x];
+
+                below.fg = cell.fg.blend(below.fg, self.blend_mode);
+                below.bg = cell.bg.blend(below.bg, self.blend_mode);
+            }
+        }
+    }
+}
+
+impl Canvas for Surface {
+    fn width(&self) -> i64 {
+        self.width
+    }
+
+    fn height(&self) -> i64 {
+        self.height
+    }
+
+    fn draw_cell(&mut self, x: i64, y: i64, cell: Cell) {
+        if x >= self.width || y >= self.height || x < 0 || y < 0 {
+            return;
+        }
+
+        let below = &mut self.cells[y as usize][x as usize];
+
+        below.c = cell.c;
+        below.style = cell.style;
+        below.fg = cell.fg.over(below.fg);
+        below.bg = cell.bg.over(below.bg);
+    }
+
+    fn cell(&self, x: i64, y: i64) -> Option<Cell> {
+        if x >= self.width || y >= self.height || x < 0 || y < 0 {
+            return None;
+        }
+
+        Some(self.cells[y as usize][x as usize])
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{Gui, TestBackend};
+
+    #[test]
+    fn translucent_colors_drawn_twice_stay_translucent() {
+        let shade = Color::rgba(0, 0, 0, 64);
+
+        let mut surface = Surface::new(1, 1);
+        surface.pixel(0, 0, ' ', shade, shade);
+        surface.pixel(0, 0, ' ', shade, shade);
+
+        assert_eq!(surface.cell(0, 0).unwrap().bg, Color::rgba(0, 0, 0, 112));
+
+        // the surface shades the gui as much as drawing the same thing twice straight on the gui
+        let mut layered = Gui::with_backend(1, 1, TestBackend::new(1, 1));
+        layered.clear(' ', Color::WHITE, Color::WHITE);
+        layered.add_layer(surface);
+        layered.display().unwrap();
+
+        let mut direct = Gui::with_backend(1, 1, TestBackend::new(1, 1));
+        direct.clear(' ', Color::WHITE, Color::WHITE);
+        direct.pixel(0, 0, ' ', shade, shade);
+        direct.pixel(0, 0, ' ', shade, shade);
+        direct.display().unwrap();
+
+        assert_eq!(layered.backend().cell(0, 0), direct.backend().cell(0, 0));
+        layered.backend().assert_bg(0, 0, 1, 1, Color::new(143, 143, 143));
+    }
+
+    #[test]
+    fn opaque_colors_replace_translucent_ones_on_a_surface() {
+        let mut surface = Surface::new(1, 1);
+        surface.pixel(0, 0, ' ', Color::TRANSPARENT, Color::rgba(0, 0, 255, 64));
+        surface.pixel(0, 0, ' ', Color::TRANSPARENT, Color::RED);
+
+        assert_eq!(surface.cell(0, 0).unwrap().bg, Color::RED);
+
+        // and translucent colors over opaque ones are mixed right away
+        surface.pixel(0, 0, ' ', Color::TRANSPARENT, Color::rgba(0, 0, 255, 128));
+        assert_eq!(surface.cell(0, 0).unwrap().bg, Color::new(127, 0, 128));
+    }
+
+    #[test]
+    fn color_over_adds_up_alpha() {
+        assert_eq!(Color::rgba(255, 0, 0, 128).over(Color::rgba(0, 0, 255, 128)), Color::rgba(170, 0, 85, 192));
+        assert_eq!(Color::rgba(255, 0, 0, 255).over(Color::rgba(0, 0, 255, 128)), Color::new(255, 0, 0));
+        assert_eq!(Color::rgba(255, 0, 0, 0).over(Color::rgba(0, 0, 255, 0)), Color::rgba(255, 0, 0, 0));
+        assert_eq!(Color::rgba(255, 0, 0, 128).over(Color::TRANSPARENT), Color::rgba(255, 0, 0, 128));
+    }
+
+    // puts a layer with 本 at the offset over a gui with the given text and returns the text of the frame
+    fn composite_text(below: &str, offset: i64) -> String {
+        let mut gui = Gui::with_backend(4, 1, TestBackend::new(4, 1));
+        gui.clear(' ', Color::WHITE, Color::BLACK);
+        gui.horizontal_text(0, 0, below, Color::WHITE, Color::BLACK);
+
+        let mut surface = Surface::new(2, 1);
+        surface.horizontal_text(0, 0, "本", Color::WHITE, Color::BLACK);
+        surface.set_offset(offset, 0);
+        gui.add_layer(surface);
+
+        gui.display().unwrap();
+        gui.backend().text()
+    }
+
+    #[test]
+    fn wide_graphemes_on_layers_dont_leave_halves_behind() {
+        // the continuation of the layer lands on the lead of the gui
+        assert_eq!(composite_text(" 日", 0), "本  ");
+
+        // the lead of the layer lands on the continuation of the gui
+        assert_eq!(composite_text("日", 1), " 本 ");
+        assert_eq!(composite_text("a日", 2), "a 本");
+
+        // the edges of the gui cut the layer in half
+        assert_eq!(composite_text("abcd", 3), "abc ");
+        assert_eq!(composite_text("abcd", -1), " bcd");
+    }
+}
@@ -0,0 +1,462 @@
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+use unicode_width::UnicodeWidthStr;
+
+use super::{Backend, Cell, Color, Style};
+
+/// Backend that keeps the displayed cells in memory instead of printing them,
+/// used to check what a gui draws without a terminal
+pub struct TestBackend {
+    width: i64,
+    height: i64,
+
+    cells: Vec<Vec<Cell>>,
+
+    frame_count: usize,
+    drawn_since_flush: bool,
+    cursor_visible: bool,
+    alternate_screen: bool,
+    scroll_region: Option<(i64, i64)>
+}
+
+impl TestBackend {
+    pub fn new(width: i64, height: i64) -> Self {
+        let cells = vec![vec![Cell::new(' ', Color::TERMINAL_DEFAULT, Color::TERMINAL_DEFAULT); width as usize]; height as usize];
+
+        Self {
+            width,
+            height,
+            cells,
+            frame_count: 0,
+            drawn_since_flush: false,
+            cursor_visible: true,
+            alternate_screen: false,
+            scroll_region: None
+        }
+    }
+
+    pub fn width(&self) -> &i64 {
+        &self.width
+    }
+
+    pub fn height(&self) -> &i64 {
+        &self.height
+    }
+
+    /// Returns the cell at the given position, None if it is outside of the backend
+    pub fn cell(&self, x: i64, y: i64) -> Option<&Cell> {
+        if x >= self.width || y >= self.height || x < 0 || y < 0 {
+            return None;
+        }
+
+        Some(&self.cells[y as usize][x as usize])
+    }
+
+    /// The number of frames that have been flushed with something drawn in them
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    pub fn alternate_screen(&self) -> bool {
+        self.alternate_screen
+    }
+
+    /// The rows that scroll as (top, bottom), inclusive, None if the whole backend scrolls
+    pub fn scroll_region(&self) -> Option<(i64, i64)> {
+        self.scroll_region
+    }
+
+    /// Returns the characters of every cell, one line per row
+    pub fn text(&self) -> String {
+        self.region_text(0, 0, self.width, self.height)
+    }
+
+    /// Returns the characters in the given region, one line per row,
+    /// parts of the region outside of the backend and continuation cells of wide graphemes are left out
+    pub fn region_text(&self, x: i64, y: i64, width: i64, height: i64) -> String {
+        self.region_lines(x, y, width, height).join("\n")
+    }
+
+    fn region_lines(&self, x: i64, y: i64, width: i64, height: i64) -> Vec<String> {
+        (y..y + height)
+            .filter(|&y| y >= 0 && y < self.height)
+            .map(|y| {
+                (x..x + width)
+                    .filter_map(|x| self.cell(x, y))
+                    .filter(|cell| !cell.c.is_continuation())
+                    .map(|cell| if cell.c.is_empty() {" ".to_string()} else {cell.c.to_string()})
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl TestBackend { // Assertions
+    /// Panics with a diff if the text of the whole backend doesnt match the expected text
+    #[track_caller]
+    pub fn assert_text(&self, expected: &str) {
+        let actual = self.region_lines(0, 0, self.width, self.height);
+        assert_lines(expected, &actual, "text doesnt match");
+    }
+
+    /// Panics with a diff if the text starting at the given position doesnt match the expected text,
+    /// the region is as wide as the longest expected line and as tall as the number of lines
+    #[track_caller]
+    pub fn assert_region(&self, x: i64, y: i64, expected: &str) {
+        let width = expected.lines().map(|line| line.width()).max().unwrap_or(0) as i64;
+        let height = expected.lines().count() as i64;
+
+        let actual = self.region_lines(x, y, width, height);
+        assert_lines(expected, &actual, &format!("region at ({}, {}) doesnt match", x, y));
+    }
+
+    /// Compares the text of the whole backend to a golden snapshot file,
+    /// the file is written instead when NOX_UPDATE_SNAPSHOTS is set
+    ///
+    /// A missing snapshot panics so a wrong path or a snapshot that was never committed doesnt pass silently
+    #[track_caller]
+    pub fn assert_snapshot<P: AsRef<Path>>(&self, path: P) {
+        self.check_snapshot(path.as_ref(), std::env::var_os("NOX_UPDATE_SNAPSHOTS").is_some());
+    }
+
+    #[track_caller]
+    fn check_snapshot(&self, path: &Path, update: bool) {
+        let actual = self.text();
+
+        if update {
+            std::fs::write(path, &actual).unwrap_or_else(|err| panic!("failed to write snapshot {}: {}", path.display(), err));
+            return;
+        }
+
+        if !path.exists() {
+            panic!("snapshot {} doesnt exist, run with NOX_UPDATE_SNAPSHOTS=1 to create it", path.display());
+        }
+
+        let expected = std::fs::read_to_string(path).unwrap_or_else(|err| panic!("failed to read snapshot {}: {}", path.display(), err));
+        let actual = self.region_lines(0, 0, self.width, self.height);
+        assert_lines(&expected, &actual, &format!("snapshot {} doesnt match", path.display()));
+    }
+
+    /// Panics if the cell at the given position isnt the expected cell
+    #[track_caller]
+    pub fn assert_cell(&self, x: i64, y: i64, expected: Cell) {
+        match self.cell(x, y) {
+            Some(cell) if *cell == expected => (),
+            Some(cell) => panic!("cell at ({}, {}) doesnt match\nexpected: {:?}\n   found: {:?}", x, y, expected, cell),
+            None => panic!("cell at ({}, {}) is outside of the {}x{} backend", x, y, self.width, self.height)
+        }
+    }
+
+    /// Panics if any cell in the given region doesnt have the expected foreground color
+    #[track_caller]
+    pub fn assert_fg(&self, x: i64, y: i64, width: i64, height: i64, expected: Color) {
+        self.assert_region_cells(x, y, width, height, "foreground color", |cell| cell.fg == expected, |cell| format!("{:?}", cell.fg));
+    }
+
+    /// Panics if any cell in the given region doesnt have the expected background color
+    #[track_caller]
+    pub fn assert_bg(&self, x: i64, y: i64, width: i64, height: i64, expected: Color) {
+        self.assert_region_cells(x, y, width, height, "background color", |cell| cell.bg == expected, |cell| format!("{:?}", cell.bg));
+    }
+
+    /// Panics if any cell in the given region doesnt have exactly the expected text attributes
+    #[track_caller]
+    pub fn assert_style(&self, x: i64, y: i64, width: i64, height: i64, expected: Style) {
+        self.assert_region_cells(x, y, width, height, "style", |cell| cell.style == expected, |cell| format!("{:?}", cell.style));
+    }
+
+    #[track_caller]
+    #[allow(clippy::too_many_arguments)]
+    fn assert_region_cells<F, D>(&self, x: i64, y: i64, width: i64, height: i64, what: &str, matches: F, describe: D)
+    where
+        F: Fn(&Cell) -> bool,
+        D: Fn(&Cell) -> String
+    {
+        let mut mismatches = String::new();
+
+        for y in y..y + height {
+            for x in x..x + width {
+                match self.cell(x, y) {
+                    Some(cell) if matches(cell) => (),
+                    Some(cell) => writeln!(mismatches, "  ({}, {}) has {}", x, y, describe(cell)).unwrap(),
+                    None => writeln!(mismatches, "  ({}, {}) is outside of the backend", x, y).unwrap()
+                }
+            }
+        }
+
+        if !mismatches.is_empty() {
+            panic!("{} doesnt match in region ({}, {}) {}x{}\n{}", what, x, y, width, height, mismatches);
+        }
+    }
+}
+
+impl Backend for TestBackend {
+    fn draw(&mut self, cells: &[(i64, i64, Cell)]) -> io::Result<()> {
+        for &(x, y, cell) in cells {
+            if x >= self.width || y >= self.height || x < 0 || y < 0 {
+                continue;
+            }
+
+            self.cells[y as usize][x as usize] = cell;
+            self.drawn_since_flush = true;
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        for row in self.cells.iter_mut() {
+            for cell in row.iter_mut() {
+                *cell = Cell::new(' ', Color::TERMINAL_DEFAULT, Color::TERMINAL_DEFAULT);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.cursor_visible = false;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.cursor_visible = true;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.drawn_since_flush {
+            self.frame_count += 1;
+            self.drawn_since_flush = false;
+        }
+
+        Ok(())
+    }
+
+    fn enter_alternate_screen(&mut self) -> io::Result<()> {
+        self.alternate_screen = true;
+        Ok(())
+    }
+
+    fn leave_alternate_screen(&mut self) -> io::Result<()> {
+        self.alternate_screen = false;
+        Ok(())
+    }
+
+    fn set_scroll_region(&mut self, top: i64, bottom: i64) -> io::Result<()> {
+        self.scroll_region = Some((top, bottom));
+        Ok(())
+    }
+
+    fn reset_scroll_region(&mut self) -> io::Result<()> {
+        self.scroll_region = None;
+        Ok(())
+    }
+}
+
+/// Panics with a line by line diff if the lines dont match
+#[track_caller]
+fn assert_lines(expected: &str, actual: &[String], message: &str) {
+    let expected = expected.lines().collect::<Vec<_>>();
+
+    if expected.len() == actual.len() && expected.iter().zip(actual).all(|(e, a)| e == a) {
+        return;
+    }
+
+    panic!("{}\n{}", message, diff_lines(&expected, actual));
+}
+
+// unchanged lines start with a space, expected lines with a - and actual lines with a +,
+// the line under a changed pair points at the columns that differ
+//     0 |abc|
+// -   1 |def|
+// +   1 |dxf|
+//         ^
+fn diff_lines(expected: &[&str], actual: &[String]) -> String {
+    let mut diff = String::new();
+
+    for i in 0..expected.len().max(actual.len()) {
+        let e = expected.get(i).copied();
+        let a = actual.get(i).map(String::as_str);
+
+        if e == a {
+            writeln!(diff, "  {:>3} |{}|", i, a.unwrap_or("")).unwrap();
+            continue;
+        }
+
+        if let Some(e) = e {
+            writeln!(diff, "- {:>3} |{}|", i, e).unwrap();
+        }
+
+        if let Some(a) = a {
+            writeln!(diff, "+ {:>3} |{}|", i, a).unwrap();
+        }
+
+        if let (Some(e), Some(a)) = (e, a) {
+            let e = e.chars().collect::<Vec<_>>();
+            let a = a.chars().collect::<Vec<_>>();
+
+            let markers = (0..e.len().max(a.len()))
+                .map(|col| if e.get(col) == a.get(col) {' '} else {'^'})
+                .collect::<String>();
+
+            writeln!(diff, "       {}", markers.trim_end()).unwrap();
+        }
+    }
+
+    diff
+}
+
+
+
+// guis drawn through a test backend, for the tests of the drawing functions
+#[cfg(test)]
+pub(super) mod test_gui {
+    use super::TestBackend;
+    use crate::graphics::{Color, Gui};
+
+    pub fn gui(width: i64, height: i64) -> Gui<TestBackend> {
+        Gui::with_backend(width, height, TestBackend::new(width, height))
+    }
+
+    // a gui full of dots so the cells that werent drawn on can be told apart
+    pub fn dotted_gui(width: i64, height: i64) -> Gui<TestBackend> {
+        let mut gui = gui(width, height);
+        gui.clear('.', Color::WHITE, Color::BLACK);
+        gui
+    }
+
+    // displays the gui and returns its text, one line per row
+    pub fn text(gui: &mut Gui<TestBackend>) -> String {
+        gui.display().unwrap();
+        gui.backend().text()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::Gui;
+
+    // a 6x4 gui with a line along the top, a box below it and some text in the box
+    fn drawn_gui() -> Gui<TestBackend> {
+        let mut gui = Gui::with_backend(6, 4, TestBackend::new(6, 4));
+
+        gui.line(0, 0, 5, 0, '-', Color::WHITE, Color::BLACK);
+        gui.rect(0, 1, 6, 3, '#', ' ', Color::new(255, 0, 0), Color::BLACK, Color::WHITE, Color::new(0, 0, 255));
+        gui.horizontal_text(1, 2, "hi", Color::new(0, 255, 0), Color::new(0, 0, 255));
+        gui.display().unwrap();
+
+        gui
+    }
+
+    fn snapshot_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("nox_lib_{}_{}.txt", name, std::process::id()))
+    }
+
+    #[test]
+    fn text_matches() {
+        drawn_gui().backend().assert_text("------\n######\n#hi  #\n######");
+    }
+
+    #[test]
+    #[should_panic(expected = "text doesnt match")]
+    fn text_mismatch_panics() {
+        drawn_gui().backend().assert_text("------\n######\n#ho  #\n######");
+    }
+
+    #[test]
+    fn region_matches() {
+        drawn_gui().backend().assert_region(0, 2, "#hi");
+    }
+
+    #[test]
+    #[should_panic(expected = "region at (1, 2) doesnt match")]
+    fn region_mismatch_panics() {
+        drawn_gui().backend().assert_region(1, 2, "hello");
+    }
+
+    #[test]
+    fn cell_matches() {
+        let gui = drawn_gui();
+
+        gui.backend().assert_cell(3, 0, Cell::new('-', Color::WHITE, Color::BLACK));
+        gui.backend().assert_cell(2, 2, Cell::new('i', Color::new(0, 255, 0), Color::new(0, 0, 255)));
+    }
+
+    #[test]
+    #[should_panic(expected = "cell at (0, 1) doesnt match")]
+    fn cell_mismatch_panics() {
+        drawn_gui().backend().assert_cell(0, 1, Cell::new('#', Color::WHITE, Color::BLACK));
+    }
+
+    #[test]
+    #[should_panic(expected = "is outside of the 6x4 backend")]
+    fn cell_outside_panics() {
+        drawn_gui().backend().assert_cell(6, 0, Cell::new(' ', Color::TERMINAL_DEFAULT, Color::TERMINAL_DEFAULT));
+    }
+
+    #[test]
+    fn colors_match() {
+        let gui = drawn_gui();
+
+        gui.backend().assert_fg(0, 0, 6, 1, Color::WHITE);
+        gui.backend().assert_bg(1, 2, 4, 1, Color::new(0, 0, 255));
+        gui.backend().assert_style(0, 0, 6, 4, Style::NONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "(0, 2) has")]
+    fn color_mismatch_panics() {
+        drawn_gui().backend().assert_bg(0, 2, 4, 1, Color::new(0, 0, 255));
+    }
+
+    #[test]
+    fn diff_points_at_the_changed_columns() {
+        let diff = diff_lines(&["abc", "def"], &["abc".to_string(), "dxf".to_string()]);
+
+        assert_eq!(diff, "    0 |abc|\n-   1 |def|\n+   1 |dxf|\n        ^\n");
+    }
+
+    #[test]
+    fn diff_shows_missing_and_extra_lines() {
+        let diff = diff_lines(&["a"], &["a".to_string(), "b".to_string()]);
+
+        assert_eq!(diff, "    0 |a|\n+   1 |b|\n");
+    }
+
+    #[test]
+    fn snapshot_matches() {
+        let path = snapshot_path("snapshot_matches");
+        let gui = drawn_gui();
+
+        gui.backend().check_snapshot(&path, true);
+        gui.backend().check_snapshot(&path, false);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "doesnt match")]
+    fn snapshot_mismatch_panics() {
+        let path = snapshot_path("snapshot_mismatch_panics");
+        std::fs::write(&path, "------\n######\n#ho  #\n######").unwrap();
+
+        let result = std::panic::catch_unwind(|| drawn_gui().backend().check_snapshot(&path, false));
+        std::fs::remove_file(&path).unwrap();
+
+        std::panic::resume_unwind(result.unwrap_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "doesnt exist")]
+    fn missing_snapshot_panics() {
+        drawn_gui().backend().check_snapshot(&snapshot_path("missing_snapshot_panics"), false);
+    }
+}
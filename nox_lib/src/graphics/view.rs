@@ -0,0 +1,70 @@
+use super::{Canvas, Cell, Color, Grapheme, Style};
+
+/// A rectangular part of a canvas that can be drawn on by itself, see Canvas::view
+///
+/// Coordinates start at the top left corner of the view and anything drawn outside of it is cut off,
+/// so it doesnt spill over into the rest of the canvas
+pub struct View<'a, C: Canvas + ?Sized> {
+    canvas: &'a mut C,
+
+    // where the top left corner of the view is on the canvas
+    x: i64,
+    y: i64,
+
+    width: i64,
+    height: i64
+}
+
+impl<'a, C: Canvas + ?Sized> View<'a, C> {
+    pub fn new(canvas: &'a mut C, x: i64, y: i64, width: i64, height: i64) -> Self {
+        Self {canvas, x, y, width: width.max(0), height: height.max(0)}
+    }
+
+    /// Where the top left corner of the view is on the canvas it was made from
+    pub fn position(&self) -> (i64, i64) {
+        (self.x, self.y)
+    }
+}
+
+impl<C: Canvas + ?Sized> Canvas for View<'_, C> {
+    fn width(&self) -> i64 {
+        self.width
+    }
+
+    fn height(&self) -> i64 {
+        self.height
+    }
+
+    fn draw_cell(&mut self, x: i64, y: i64, cell: Cell) {
+        if x >= self.width || y >= self.height || x < 0 || y < 0 {
+            return;
+        }
+
+        self.canvas.draw_cell(self.x + x, self.y + y, cell);
+    }
+
+    // the canvas takes care of wide graphemes so the ones that stick out of the view can be broken up too
+    fn grapheme(&mut self, x: i64, y: i64, grapheme: Grapheme, fg: Color, bg: Color, style: Style) {
+        if x >= self.width || y >= self.height || x < 0 || y < 0 {
+            return;
+        }
+
+        // wide graphemes that would be cut in half by the right edge dont fit
+        let grapheme = if grapheme.width() == 2 && x + 1 >= self.width {
+            Grapheme::from(' ')
+        }
+        else {
+            grapheme
+        };
+
+        self.canvas.grapheme(self.x + x, self.y + y, grapheme, fg, bg, style);
+    }
+
+    fn cell(&self, x: i64, y: i64) -> Option<Cell> {
+        if x >= self.width || y >= self.height || x < 0 || y < 0 {
+            return None;
+        }
+
+        self.canvas.cell(self.x + x, self.y + y)
+    }
+}
@@ -0,0 +1,816 @@
+use std::io::{self, Write};
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+use super::{Cell, Color, Grapheme, Style};
+
+/// A small vt100/xterm emulator that turns the bytes written to it back into a grid of cells,
+/// it implements Write so it can be used as the output of an AnsiBackend
+///
+/// Supports printable utf-8 text, carriage return, line feed, backspace, tab,
+/// cursor movement, erasing, 24 bit, 256 and 16 color sgr codes, text attributes, cursor visibility,
+/// scroll regions and the alternate screen, anything else is parsed and ignored
+pub struct VtScreen {
+    width: i64,
+    height: i64,
+
+    cells: Vec<Vec<Cell>>,
+
+    // the normal screen while the alternate screen is active
+    saved_screen: Option<Vec<Vec<Cell>>>,
+
+    // the rows that scroll, inclusive
+    scroll_top: i64,
+    scroll_bottom: i64,
+
+    cursor_x: i64,
+    cursor_y: i64,
+    saved_cursor: (i64, i64),
+    cursor_visible: bool,
+
+    // set after printing in the last column, the next printed character goes on the next line
+    pending_wrap: bool,
+
+    // the cell of the last grapheme that was printed, combining marks and the like are only added to it
+    // when nothing else happened since it was printed
+    last_print: Option<(i64, i64)>,
+
+    fg: Color,
+    bg: Color,
+    style: Style,
+
+    state: State,
+    params: Vec<u16>,
+    private_marker: Option<u8>,
+    utf8_buffer: Vec<u8>
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Ground,
+    Escape,
+    Csi,
+    Osc,
+    OscEscape
+}
+
+impl VtScreen {
+    pub fn new(width: i64, height: i64) -> Self {
+        let cells = vec![vec![Cell::new(' ', Color::TERMINAL_DEFAULT, Color::TERMINAL_DEFAULT); width as usize]; height as usize];
+
+        Self {
+            width,
+            height,
+            cells,
+            saved_screen: None,
+            scroll_top: 0,
+            scroll_bottom: height - 1,
+            cursor_x: 0,
+            cursor_y: 0,
+            saved_cursor: (0, 0),
+            cursor_visible: true,
+            pending_wrap: false,
+            last_print: None,
+            fg: Color::TERMINAL_DEFAULT,
+            bg: Color::TERMINAL_DEFAULT,
+            style: Style::NONE,
+            state: State::Ground,
+            params: Vec::new(),
+            private_marker: None,
+            utf8_buffer: Vec::with_capacity(4)
+        }
+    }
+
+    pub fn width(&self) -> &i64 {
+        &self.width
+    }
+
+    pub fn height(&self) -> &i64 {
+        &self.height
+    }
+
+    /// Returns the cell at the given position, None if it is outside of the screen
+    pub fn cell(&self, x: i64, y: i64) -> Option<&Cell> {
+        if x >= self.width || y >= self.height || x < 0 || y < 0 {
+            return None;
+        }
+
+        Some(&self.cells[y as usize][x as usize])
+    }
+
+    /// Every cell on the screen, indexed by row then column
+    pub fn cells(&self) -> &[Vec<Cell>] {
+        &self.cells
+    }
+
+    /// Returns the characters of every cell, one line per row, continuation cells of wide graphemes are left out
+    pub fn text(&self) -> String {
+        self.cells.iter()
+            .map(|row| row.iter().map(|cell| cell.c.as_str()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The position of the cursor as (x, y)
+    pub fn cursor(&self) -> (i64, i64) {
+        (self.cursor_x, self.cursor_y)
+    }
+
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    pub fn alternate_screen(&self) -> bool {
+        self.saved_screen.is_some()
+    }
+
+    /// The rows that scroll as (top, bottom), inclusive
+    pub fn scroll_region(&self) -> (i64, i64) {
+        (self.scroll_top, self.scroll_bottom)
+    }
+
+    /// Feeds bytes to the emulator, sequences split across calls are picked up where they left off
+    pub fn process(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.process_byte(byte);
+        }
+    }
+}
+
+impl VtScreen { // Parsing
+    fn process_byte(&mut self, byte: u8) {
+        match self.state {
+            State::Ground => self.ground(byte),
+            State::Escape => self.escape(byte),
+            State::Csi => self.csi(byte),
+            State::Osc => match byte {
+                0x07 => self.state = State::Ground,
+                0x1b => self.state = State::OscEscape,
+                _ => ()
+            },
+            State::OscEscape => self.state = if byte == b'\\' {State::Ground} else {State::Osc}
+        }
+    }
+
+    fn ground(&mut self, byte: u8) {
+        if !self.utf8_buffer.is_empty() || byte >= 0x80 {
+            self.utf8(byte);
+            return;
+        }
+
+        if let 0x20..=0x7e = byte {
+            self.print(byte as char);
+            return;
+        }
+
+        // a combining mark after a cursor move or any other sequence doesnt belong to the grapheme printed before it
+        self.last_print = None;
+
+        match byte {
+            0x1b => self.state = State::Escape,
+            b'\r' => self.carriage_return(),
+            b'\n' | 0x0b | 0x0c => self.line_feed(),
+            0x08 => self.move_cursor(self.cursor_x - 1, self.cursor_y),
+            b'\t' => self.move_cursor((self.cursor_x / 8 + 1) * 8, self.cursor_y),
+            _ => () // other control characters dont change the screen
+        }
+    }
+
+    fn utf8(&mut self, byte: u8) {
+        // a byte that cant continue the sequence ends it and is then handled on its own
+        if !self.utf8_buffer.is_empty() && byte & 0xc0 != 0x80 {
+            self.utf8_buffer.clear();
+            self.print(char::REPLACEMENT_CHARACTER);
+            self.ground(byte);
+            return;
+        }
+
+        self.utf8_buffer.push(byte);
+
+        let expected_len = match self.utf8_buffer[0] {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1 // stray continuation byte or invalid lead byte
+        };
+
+        if self.utf8_buffer.len() < expected_len {
+            return;
+        }
+
+        let c = std::str::from_utf8(&self.utf8_buffer).ok()
+            .and_then(|s| s.chars().next())
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+
+        self.utf8_buffer.clear();
+        self.print(c);
+    }
+
+    fn escape(&mut self, byte: u8) {
+        self.state = State::Ground;
+
+        match byte {
+            b'[' => {
+                self.params.clear();
+                self.params.push(0);
+                self.private_marker = None;
+                self.state = State::Csi;
+            },
+            b']' => self.state = State::Osc,
+            b'7' => self.saved_cursor = (self.cursor_x, self.cursor_y),
+            b'8' => self.move_cursor(self.saved_cursor.0, self.saved_cursor.1),
+            b'D' => self.line_feed(),
+            b'E' => {
+                self.carriage_return();
+                self.line_feed();
+            },
+            b'M' => self.reverse_line_feed(),
+            b'c' => *self = Self::new(self.width, self.height),
+            _ => ()
+        }
+    }
+
+    fn csi(&mut self, byte: u8) {
+        match byte {
+            b'0'..=b'9' => {
+                let param = self.params.last_mut().unwrap();
+                *param = param.saturating_mul(10).saturating_add((byte - b'0') as u16);
+            },
+            b';' | b':' => self.params.push(0),
+            b'<'..=b'?' => self.private_marker = Some(byte),
+            0x20..=0x2f => (), // intermediate bytes arent used by anything supported
+            0x40..=0x7e => {
+                self.state = State::Ground;
+                self.dispatch_csi(byte);
+            },
+            0x1b => self.state = State::Escape,
+            _ => ()
+        }
+    }
+
+    // returns the parameter at the index, missing and zero parameters become the default
+    fn param(&self, index: usize, default: u16) -> i64 {
+        match self.params.get(index) {
+            Some(&0) | None => default as i64,
+            Some(&param) => param as i64
+        }
+    }
+
+    fn dispatch_csi(&mut self, byte: u8) {
+        if self.private_marker == Some(b'?') {
+            let enable = match byte {
+                b'h' => true,
+                b'l' => false,
+                _ => return
+            };
+
+            for param in self.params.clone() {
+                match param {
+                    25 => self.cursor_visible = enable,
+                    47 | 1047 | 1049 => self.switch_screen(enable),
+                    _ => ()
+                }
+            }
+
+            return;
+        }
+
+        if self.private_marker.is_some() {
+            return;
+        }
+
+        let (x, y) = (self.cursor_x, self.cursor_y);
+
+        match byte {
+            b'H' | b'f' => self.move_cursor(self.param(1, 1) - 1, self.param(0, 1) - 1),
+            b'A' => self.move_cursor(x, y - self.param(0, 1)),
+            b'B' => self.move_cursor(x, y + self.param(0, 1)),
+            b'C' => self.move_cursor(x + self.param(0, 1), y),
+            b'D' => self.move_cursor(x - self.param(0, 1), y),
+            b'E' => self.move_cursor(0, y + self.param(0, 1)),
+            b'F' => self.move_cursor(0, y - self.param(0, 1)),
+            b'G' | b'`' => self.move_cursor(self.param(0, 1) - 1, y),
+            b'd' => self.move_cursor(x, self.param(0, 1) - 1),
+            b'J' => self.erase_display(self.param(0, 0)),
+            b'K' => self.erase_line(self.param(0, 0)),
+            b'm' => self.sgr(),
+            b'r' => self.set_scroll_region(self.param(0, 1) - 1, self.param(1, self.height as u16) - 1),
+            b'S' => (0..self.param(0, 1)).for_each(|_| self.scroll_up()),
+            b'T' => (0..self.param(0, 1)).for_each(|_| self.scroll_down()),
+            b's' => self.saved_cursor = (x, y),
+            b'u' => self.move_cursor(self.saved_cursor.0, self.saved_cursor.1),
+            _ => ()
+        }
+    }
+
+    fn sgr(&mut self) {
+        let params = std::mem::take(&mut self.params);
+        let mut i = 0;
+
+        while i < params.len() {
+            match params[i] {
+                0 => {
+                    self.fg = Color::TERMINAL_DEFAULT;
+                    self.bg = Color::TERMINAL_DEFAULT;
+                    self.style = Style::NONE;
+                },
+                code @ 30..=37 => self.fg = Color::from_ansi256((code - 30) as u8),
+                code @ 40..=47 => self.bg = Color::from_ansi256((code - 40) as u8),
+                code @ 90..=97 => self.fg = Color::from_ansi256((code - 90 + 8) as u8),
+                code @ 100..=107 => self.bg = Color::from_ansi256((code - 100 + 8) as u8),
+                39 => self.fg = Color::TERMINAL_DEFAULT,
+                49 => self.bg = Color::TERMINAL_DEFAULT,
+                code @ (38 | 48) => {
+                    let color = match params.get(i + 1) {
+                        Some(2) if i + 4 < params.len() => {
+                            let color = Color::new(params[i + 2] as u8, params[i + 3] as u8, params[i + 4] as u8);
+                            i += 4;
+                            Some(color)
+                        },
+                        Some(5) if i + 2 < params.len() => {
+                            let color = Color::from_ansi256(params[i + 2] as u8);
+                            i += 2;
+                            Some(color)
+                        },
+                        _ => None
+                    };
+
+                    if let Some(color) = color {
+                        if code == 38 {
+                            self.fg = color;
+                        }
+                        else {
+                            self.bg = color;
+                        }
+                    }
+                },
+                code => self.style.apply_sgr(code)
+            }
+
+            i += 1;
+        }
+
+        self.params = params;
+    }
+}
+
+impl VtScreen { // Screen Operations
+    fn blank(&self) -> Cell {
+        Cell::new(' ', Color::TERMINAL_DEFAULT, self.bg)
+    }
+
+    fn print(&mut self, c: char) {
+        // combining marks, emoji modifiers and the like are added to the grapheme before them
+        if self.append_to_previous(c) {
+            return;
+        }
+
+        let width = c.width().unwrap_or(1).clamp(1, 2) as i64;
+
+        // wide chars that dont fit at the end of the line go on the next one
+        if self.pending_wrap || (width == 2 && self.cursor_x + 1 >= self.width) {
+            self.carriage_return();
+            self.line_feed();
+        }
+
+        let (x, y) = (self.cursor_x as usize, self.cursor_y as usize);
+        self.last_print = Some((self.cursor_x, self.cursor_y));
+
+        if let Some(row) = self.cells.get_mut(y) {
+            if let Some(cell) = row.get_mut(x) {
+                *cell = Cell::styled(c, self.fg, self.bg, self.style);
+            }
+
+            if width == 2 {
+                if let Some(cell) = row.get_mut(x + 1) {
+                    *cell = Cell::styled(Grapheme::CONTINUATION, self.fg, self.bg, self.style);
+                }
+            }
+        }
+
+        self.advance_cursor(width);
+    }
+
+    fn advance_cursor(&mut self, columns: i64) {
+        if self.cursor_x + columns >= self.width {
+            self.cursor_x = (self.width - 1).max(0);
+            self.pending_wrap = true;
+        }
+        else {
+            self.cursor_x += columns;
+        }
+    }
+
+    // adds the char to the grapheme that was just printed if they make up a single grapheme together,
+    // false if they dont or if there is nothing to add it to
+    fn append_to_previous(&mut self, c: char) -> bool {
+        let (x, y) = match self.last_print {
+            Some(position) => position,
+            None => return false
+        };
+
+        let row = match self.cells.get_mut(y as usize) {
+            Some(row) => row,
+            None => return false
+        };
+
+        let cell = match row.get_mut(x as usize) {
+            Some(cell) if !cell.c.is_empty() && !cell.c.is_continuation() => cell,
+            _ => return false
+        };
+
+        let mut text = cell.c.as_str().to_string();
+        text.push(c);
+
+        if text.graphemes(true).count() != 1 {
+            return false;
+        }
+
+        let previous_width = cell.c.width();
+        cell.c = Grapheme::new(&text);
+
+        // like a pair of regional indicators becoming a flag
+        if cell.c.width() > previous_width && !self.pending_wrap {
+            let (fg, bg, style) = (cell.fg, cell.bg, cell.style);
+
+            if let Some(cell) = row.get_mut(x as usize + 1) {
+                *cell = Cell::styled(Grapheme::CONTINUATION, fg, bg, style);
+            }
+
+            self.advance_cursor(1);
+        }
+
+        true
+    }
+
+    fn move_cursor(&mut self, x: i64, y: i64) {
+        self.cursor_x = x.clamp(0, (self.width - 1).max(0));
+        self.cursor_y = y.clamp(0, (self.height - 1).max(0));
+        self.pending_wrap = false;
+    }
+
+    fn carriage_return(&mut self) {
+        self.cursor_x = 0;
+        self.pending_wrap = false;
+    }
+
+    fn set_scroll_region(&mut self, top: i64, bottom: i64) {
+        let bottom = bottom.min(self.height - 1);
+
+        if top >= bottom {
+            return;
+        }
+
+        self.scroll_top = top;
+        self.scroll_bottom = bottom;
+        self.move_cursor(0, 0);
+    }
+
+    fn switch_screen(&mut self, alternate: bool) {
+        if alternate == self.saved_screen.is_some() {
+            return;
+        }
+
+        if alternate {
+            let blank_screen = vec![vec![Cell::new(' ', Color::TERMINAL_DEFAULT, Color::TERMINAL_DEFAULT); self.width as usize]; self.height as usize];
+            self.saved_screen = Some(std::mem::replace(&mut self.cells, blank_screen));
+        }
+        else if let Some(saved_screen) = self.saved_screen.take() {
+            self.cells = saved_screen;
+        }
+    }
+
+    fn line_feed(&mut self) {
+        // below the scroll region the cursor just stops at the bottom of the screen
+        if self.cursor_y == self.scroll_bottom {
+            self.scroll_up();
+        }
+        else if self.cursor_y + 1 < self.height {
+            self.cursor_y += 1;
+        }
+
+        self.pending_wrap = false;
+    }
+
+    fn reverse_line_feed(&mut self) {
+        if self.cursor_y == self.scroll_top {
+            self.scroll_down();
+        }
+        else if self.cursor_y > 0 {
+            self.cursor_y -= 1;
+        }
+
+        self.pending_wrap = false;
+    }
+
+    fn scroll_up(&mut self) {
+        if self.cells.is_empty() {
+            return;
+        }
+
+        let blank_row = vec![self.blank(); self.width as usize];
+        self.cells.remove(self.scroll_top as usize);
+        self.cells.insert(self.scroll_bottom as usize, blank_row);
+    }
+
+    fn scroll_down(&mut self) {
+        if self.cells.is_empty() {
+            return;
+        }
+
+        let blank_row = vec![self.blank(); self.width as usize];
+        self.cells.remove(self.scroll_bottom as usize);
+        self.cells.insert(self.scroll_top as usize, blank_row);
+    }
+
+    fn erase_display(&mut self, mode: i64) {
+        let blank = self.blank();
+        let (x, y) = (self.cursor_x as usize, self.cursor_y as usize);
+
+        for (row_index, row) in self.cells.iter_mut().enumerate() {
+            for (col_index, cell) in row.iter_mut().enumerate() {
+                let erase = match mode {
+                    0 => (row_index, col_index) >= (y, x),
+                    1 => (row_index, col_index) <= (y, x),
+                    2 | 3 => true,
+                    _ => false
+                };
+
+                if erase {
+                    *cell = blank;
+                }
+            }
+        }
+    }
+
+    fn erase_line(&mut self, mode: i64) {
+        let blank = self.blank();
+        let x = self.cursor_x as usize;
+
+        if let Some(row) = self.cells.get_mut(self.cursor_y as usize) {
+            for (col_index, cell) in row.iter_mut().enumerate() {
+                let erase = match mode {
+                    0 => col_index >= x,
+                    1 => col_index <= x,
+                    2 => true,
+                    _ => false
+                };
+
+                if erase {
+                    *cell = blank;
+                }
+            }
+        }
+    }
+}
+
+impl Write for VtScreen {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.process(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{AnsiBackend, Canvas, ColorDepth, Gui};
+
+    fn vt_gui(width: i64, height: i64) -> Gui<AnsiBackend<VtScreen>> {
+        Gui::with_backend(width, height, AnsiBackend::new(VtScreen::new(width, height)))
+    }
+
+    // draws a bit of everything so every kind of cell goes through the backend
+    fn draw_scene(gui: &mut Gui<AnsiBackend<VtScreen>>) {
+        gui.clear(' ', Color::TERMINAL_DEFAULT, Color::new(0, 0, 50));
+        gui.rect(0, 0, 10, 4, '#', '.', Color::ORANGE, Color::BLACK, Color::CYAN, Color::new(30, 30, 30));
+        gui.horizontal_text_styled(1, 1, "bold", Color::YELLOW, Color::PURPLE, Style::BOLD | Style::UNDERLINE);
+        gui.horizontal_text(1, 2, "plain", Color::new(12, 200, 90), Color::TERMINAL_DEFAULT);
+        gui.line(10, 0, 15, 3, '*', Color::MAGENTA, Color::new(200, 200, 200));
+    }
+
+    // checks that every cell the emulator ended up with is the cell the gui drew, with its colors passed through to_color
+    #[track_caller]
+    fn assert_round_trip<F: Fn(Color) -> Color>(gui: &Gui<AnsiBackend<VtScreen>>, to_color: F) {
+        let screen = gui.backend().get_ref();
+
+        for y in 0..*gui.height() {
+            for x in 0..*gui.width() {
+                let drawn = Canvas::cell(gui, x, y).unwrap();
+                let expected = Cell::styled(drawn.c, to_color(drawn.fg), to_color(drawn.bg), drawn.style);
+
+                assert_eq!(*screen.cell(x, y).unwrap(), expected, "cell at ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn full_frame_round_trips() {
+        let mut gui = vt_gui(16, 4);
+        draw_scene(&mut gui);
+        gui.display().unwrap();
+
+        assert_round_trip(&gui, |color| color);
+    }
+
+    #[test]
+    fn diff_frame_round_trips() {
+        let mut gui = vt_gui(16, 4);
+        draw_scene(&mut gui);
+        gui.display().unwrap();
+
+        gui.pixel(4, 3, '@', Color::GREEN, Color::BLACK);
+        gui.pixel_styled(12, 1, '!', Color::TERMINAL_DEFAULT, Color::BLUE, Style::REVERSE);
+        gui.horizontal_text(2, 2, "LA", Color::new(12, 200, 90), Color::TERMINAL_DEFAULT);
+        gui.display().unwrap();
+
+        assert_round_trip(&gui, |color| color);
+        assert_eq!(gui.backend().get_ref().text().lines().nth(2), Some("#pLAin...#   ** "));
+    }
+
+    #[test]
+    fn ansi256_frame_round_trips() {
+        let mut gui = vt_gui(16, 4);
+        gui.set_color_depth(ColorDepth::Ansi256);
+        draw_scene(&mut gui);
+        gui.display().unwrap();
+
+        assert_round_trip(&gui, |color| color.to_ansi256().map(Color::from_ansi256).unwrap_or(color));
+    }
+
+    #[test]
+    fn ansi16_frame_round_trips() {
+        let mut gui = vt_gui(16, 4);
+        gui.set_color_depth(ColorDepth::Ansi16);
+        draw_scene(&mut gui);
+        gui.display().unwrap();
+
+        assert_round_trip(&gui, |color| color.to_ansi16().map(Color::from_ansi256).unwrap_or(color));
+    }
+
+    #[test]
+    fn monochrome_frame_only_keeps_text_and_styles() {
+        let mut gui = vt_gui(16, 4);
+        gui.set_color_depth(ColorDepth::Monochrome);
+        draw_scene(&mut gui);
+        gui.display().unwrap();
+
+        assert_round_trip(&gui, |_| Color::TERMINAL_DEFAULT);
+    }
+
+    #[test]
+    fn wide_graphemes_round_trip() {
+        let mut gui = vt_gui(12, 2);
+        gui.horizontal_text(0, 0, "日本語", Color::WHITE, Color::BLACK);
+        gui.horizontal_text(7, 0, "🇯🇵e\u{301}", Color::RED, Color::TERMINAL_DEFAULT);
+        gui.horizontal_text(0, 1, "a👍🏽b", Color::GREEN, Color::BLUE);
+        gui.display().unwrap();
+
+        assert_round_trip(&gui, |color| color);
+
+        let screen = gui.backend().get_ref();
+        assert_eq!(screen.text(), "日本語 🇯🇵e\u{301}  \na👍🏽b        ");
+        assert!(screen.cell(1, 0).unwrap().c.is_continuation());
+        assert!(screen.cell(8, 0).unwrap().c.is_continuation());
+    }
+
+    #[test]
+    fn wide_graphemes_round_trip_after_a_diff() {
+        let mut gui = vt_gui(8, 1);
+        gui.horizontal_text(0, 0, "ab日本", Color::WHITE, Color::BLACK);
+        gui.display().unwrap();
+
+        // overwriting the left half of a wide grapheme breaks it up
+        gui.pixel(4, 0, 'x', Color::WHITE, Color::BLACK);
+        gui.display().unwrap();
+
+        assert_round_trip(&gui, |color| color);
+        assert_eq!(gui.backend().get_ref().text(), "ab日x   ");
+    }
+
+    #[test]
+    fn combining_marks_only_join_the_grapheme_printed_before_them() {
+        let mut screen = VtScreen::new(4, 1);
+        screen.process("ab\u{301}".as_bytes());
+        assert_eq!(screen.text(), "ab\u{301}  ");
+
+        // after a cursor move the mark doesnt belong to the b anymore
+        let mut screen = VtScreen::new(4, 1);
+        screen.process("ab\x1b[1;4H\u{301}".as_bytes());
+        assert_eq!(screen.text(), "ab \u{301}");
+    }
+
+    // a gui that is smaller than the terminal it is shown in, with some log output printed before it
+    fn gui_below_log(width: i64, height: i64, terminal_width: i64, terminal_height: i64, log: &str) -> Gui<AnsiBackend<VtScreen>> {
+        let mut gui = Gui::with_backend(width, height, AnsiBackend::new(VtScreen::new(terminal_width, terminal_height)));
+        print_log(&mut gui, log);
+        gui
+    }
+
+    fn print_log(gui: &mut Gui<AnsiBackend<VtScreen>>, log: &str) {
+        gui.backend_mut().get_mut().process(log.as_bytes());
+    }
+
+    fn screen_lines(gui: &Gui<AnsiBackend<VtScreen>>) -> Vec<String> {
+        gui.backend().get_ref().text().lines().map(|line| line.trim_end().to_string()).collect()
+    }
+
+    #[test]
+    fn inline_gui_is_drawn_below_the_log() {
+        let mut gui = gui_below_log(5, 2, 8, 6, "log 1\r\nlog 2\r\n");
+
+        gui.enter_inline().unwrap();
+        gui.clear('.', Color::TERMINAL_DEFAULT, Color::TERMINAL_DEFAULT);
+        gui.horizontal_text(0, 0, "ab", Color::RED, Color::TERMINAL_DEFAULT);
+        gui.horizontal_text(1, 1, "cd", Color::RED, Color::TERMINAL_DEFAULT);
+        gui.display().unwrap();
+
+        assert_eq!(screen_lines(&gui), ["log 1", "log 2", "ab...", ".cd..", "", ""]);
+
+        // the cursor ends up on the last row, so the first change has to go back up
+        gui.pixel(3, 0, 'x', Color::RED, Color::TERMINAL_DEFAULT);
+        gui.pixel(0, 1, 'y', Color::RED, Color::TERMINAL_DEFAULT);
+        gui.display().unwrap();
+
+        assert_eq!(screen_lines(&gui), ["log 1", "log 2", "ab.x.", "ycd..", "", ""]);
+
+        // the log continues on the line below the gui
+        gui.leave_inline().unwrap();
+        assert_eq!(gui.backend().get_ref().cursor(), (0, 4));
+
+        print_log(&mut gui, "log 3\r\n");
+        assert_eq!(screen_lines(&gui), ["log 1", "log 2", "ab.x.", "ycd..", "log 3", ""]);
+    }
+
+    #[test]
+    fn inline_gui_scrolls_the_log_up_to_fit() {
+        let mut gui = gui_below_log(5, 2, 8, 4, "log 1\r\nlog 2\r\nlog 3\r\n");
+
+        gui.enter_inline().unwrap();
+        gui.clear('.', Color::TERMINAL_DEFAULT, Color::TERMINAL_DEFAULT);
+        gui.horizontal_text(0, 0, "ab", Color::RED, Color::TERMINAL_DEFAULT);
+        gui.display().unwrap();
+
+        assert_eq!(screen_lines(&gui), ["log 2", "log 3", "ab...", "....."]);
+
+        gui.leave_inline().unwrap();
+        print_log(&mut gui, "log 4");
+        assert_eq!(screen_lines(&gui), ["log 3", "ab...", ".....", "log 4"]);
+    }
+
+    #[test]
+    fn alternate_screen_gives_the_log_back() {
+        let mut gui = gui_below_log(4, 1, 4, 2, "log\r\n$");
+
+        gui.enter_alternate_screen().unwrap();
+        assert!(gui.backend().get_ref().alternate_screen());
+        assert_eq!(screen_lines(&gui), ["", ""]);
+
+        gui.clear('#', Color::TERMINAL_DEFAULT, Color::TERMINAL_DEFAULT);
+        gui.display().unwrap();
+        assert_eq!(screen_lines(&gui), ["####", ""]);
+
+        gui.leave_alternate_screen().unwrap();
+        assert!(!gui.backend().get_ref().alternate_screen());
+        assert_eq!(screen_lines(&gui), ["log", "$"]);
+    }
+
+    #[test]
+    fn log_scrolls_above_a_docked_gui() {
+        let mut gui = gui_below_log(6, 2, 8, 5, "log 1\r\nlog 2\r\nlog 3\r\nlog 4\r\n");
+
+        // room is made for the gui by scrolling the log up, and the cursor is left where the next line of log goes
+        gui.dock_bottom(5).unwrap();
+        assert_eq!(gui.backend().get_ref().scroll_region(), (0, 2));
+        assert_eq!(gui.backend().get_ref().cursor(), (0, 2));
+
+        gui.clear('=', Color::TERMINAL_DEFAULT, Color::TERMINAL_DEFAULT);
+        gui.display().unwrap();
+
+        assert_eq!(screen_lines(&gui), ["log 3", "log 4", "", "======", "======"]);
+        assert_eq!(gui.backend().get_ref().cursor(), (0, 2));
+
+        // the log only scrolls in the rows above the gui
+        print_log(&mut gui, "log 5\r\nlog 6\r\n");
+        assert_eq!(screen_lines(&gui), ["log 5", "log 6", "", "======", "======"]);
+
+        // drawing in between lines of log doesnt move the cursor
+        print_log(&mut gui, "log");
+        gui.pixel(0, 1, '#', Color::TERMINAL_DEFAULT, Color::TERMINAL_DEFAULT);
+        gui.display().unwrap();
+        print_log(&mut gui, " 7\r\n");
+
+        assert_eq!(screen_lines(&gui), ["log 6", "log 7", "", "======", "#====="]);
+
+        gui.undock().unwrap();
+        assert_eq!(gui.backend().get_ref().scroll_region(), (0, 4));
+        assert_eq!(gui.backend().get_ref().cursor(), (0, 2));
+    }
+}
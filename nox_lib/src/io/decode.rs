@@ -0,0 +1,437 @@
+use std::time::Duration;
+
+use super::event::Event;
+use super::key::{byte_key, csi_key, ss3_key, Key, KeyCode, Modifiers};
+use super::mouse::{sgr_mouse, x10_mouse};
+
+/// Where the decoder gets the bytes after the first one of an event from
+pub(super) trait ByteSource {
+    /// Returns the next byte, or None if it doesnt show up in time,
+    /// which is how a lone escape press is told apart from the start of a sequence
+    fn next_byte(&mut self) -> Result<Option<u8>, &'static str>;
+
+    /// Returns the next byte, or None if it doesnt show up within the timeout
+    fn next_byte_within(&mut self, timeout: Duration) -> Result<Option<u8>, &'static str>;
+
+    /// Puts a byte back so it is returned by the next call to next_byte
+    fn unread(&mut self, byte: u8);
+}
+
+// pastes can be big enough to arrive in several reads, so the end of one gets more time than a key sequence
+const PASTE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Turns the bytes of a key press, mouse report, paste or focus change into an event, returns None for sequences that arent supported
+pub(super) fn decode_event<S: ByteSource>(first: u8, source: &mut S) -> Result<Option<Event>, &'static str> {
+    if first != 0x1b {
+        return Ok(Some(Event::Key(decode_single(first, source)?)));
+    }
+
+    let second = match source.next_byte()? {
+        None => return Ok(Some(Event::Key(Key::new(KeyCode::Esc)))),
+        Some(byte) => byte
+    };
+
+    let event = match second {
+        b'[' => decode_csi(source)?,
+        b'O' => match source.next_byte()? {
+            None => Some(Key::with_modifiers(KeyCode::Char('O'), Modifiers::ALT)),
+            Some(byte) => ss3_key(byte, 1)
+        }.map(Event::Key),
+        0x1b => Some(Event::Key(Key::with_modifiers(KeyCode::Esc, Modifiers::ALT))),
+        byte => {
+            let mut key = decode_single(byte, source)?;
+            key.modifiers.alt = true;
+            Some(Event::Key(key))
+        }
+    };
+
+    Ok(event)
+}
+
+/// Turns a utf-8 lead byte and the continuation bytes after it into a char
+///
+/// Invalid sequences become U+FFFD, a byte that cant continue the sequence is put back
+/// so it gets read on its own
+pub(super) fn decode_utf8<S: ByteSource>(first: u8, source: &mut S) -> Result<char, &'static str> {
+    let len = match first {
+        0x00..=0x7f => return Ok(first as char),
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return Ok(char::REPLACEMENT_CHARACTER) // continuation byte or a lead byte that is never valid
+    };
+
+    let mut bytes = [first, 0, 0, 0];
+
+    for byte in bytes.iter_mut().take(len).skip(1) {
+        match source.next_byte()? {
+            Some(next) if next & 0xc0 == 0x80 => *byte = next,
+            Some(next) => {
+                source.unread(next);
+                return Ok(char::REPLACEMENT_CHARACTER);
+            },
+            None => return Ok(char::REPLACEMENT_CHARACTER)
+        }
+    }
+
+    // catches overlong encodings and surrogates that got past the lead byte check
+    let c = std::str::from_utf8(&bytes[..len]).ok()
+        .and_then(|s| s.chars().next())
+        .unwrap_or(char::REPLACEMENT_CHARACTER);
+
+    Ok(c)
+}
+
+// decodes a key that starts with a byte other than escape
+fn decode_single<S: ByteSource>(byte: u8, source: &mut S) -> Result<Key, &'static str> {
+    if byte >= 0x80 {
+        return Ok(Key::new(KeyCode::Char(decode_utf8(byte, source)?)));
+    }
+
+    Ok(byte_key(byte))
+}
+
+// decodes the rest of a sequence that started with \x1b[
+// | private marker | |    parameters    | | final byte |
+//        <              0 ; 12 ; 5               M
+fn decode_csi<S: ByteSource>(source: &mut S) -> Result<Option<Event>, &'static str> {
+    let mut private_marker = None;
+    let mut params: Vec<u16> = vec![0];
+    let mut has_params = false;
+
+    let final_byte = loop {
+        let byte = match source.next_byte()? {
+            None => return Ok(None), // the sequence was cut off
+            Some(byte) => byte
+        };
+
+        match byte {
+            b'0'..=b'9' => {
+                let param = params.last_mut().unwrap();
+                *param = param.saturating_mul(10).saturating_add((byte - b'0') as u16);
+                has_params = true;
+            },
+            b';' => params.push(0),
+            b'<'..=b'?' => private_marker = Some(byte),
+            0x40..=0x7e => break byte,
+            _ => () // intermediate bytes arent used by anything supported
+        }
+    };
+
+    match (private_marker, final_byte) {
+        (Some(b'<'), b'M' | b'm') => Ok(sgr_mouse(&params, final_byte == b'M').map(Event::Mouse)),
+
+        // the x10 mouse report is followed by three raw bytes instead of parameters
+        (None, b'M') if !has_params => {
+            let mut bytes = [0; 3];
+
+            for byte in bytes.iter_mut() {
+                match source.next_byte()? {
+                    None => return Ok(None),
+                    Some(next) => *byte = next
+                }
+            }
+
+            Ok(x10_mouse(bytes[0], bytes[1], bytes[2]).map(Event::Mouse))
+        },
+
+        (None, b'~') if params[0] == 200 => decode_paste(source),
+        (None, b'I') if !has_params => Ok(Some(Event::FocusGained)),
+        (None, b'O') if !has_params => Ok(Some(Event::FocusLost)),
+
+        (None, _) => Ok(csi_key(&params, final_byte).map(Event::Key)),
+        _ => Ok(None)
+    }
+}
+
+// reads the pasted text up to the sequence that ends the paste
+// | start of paste | | text | | end of paste |
+//   \x1b[200~         ...      \x1b[201~
+fn decode_paste<S: ByteSource>(source: &mut S) -> Result<Option<Event>, &'static str> {
+    const END: &[u8] = b"\x1b[201~";
+
+    let mut bytes = Vec::new();
+
+    while !bytes.ends_with(END) {
+        match source.next_byte_within(PASTE_TIMEOUT)? {
+            None => break, // the end never showed up, so use what was pasted so far
+            Some(byte) => bytes.push(byte)
+        }
+    }
+
+    if bytes.ends_with(END) {
+        bytes.truncate(bytes.len() - END.len());
+    }
+
+    Ok(Some(Event::Paste(String::from_utf8_lossy(&bytes).into_owned())))
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::io::{MouseButton, MouseEvent, MouseKind};
+
+    // a source that has every byte ready, running out is the same as a byte not showing up in time
+    struct Bytes(VecDeque<u8>);
+
+    impl ByteSource for Bytes {
+        fn next_byte(&mut self) -> Result<Option<u8>, &'static str> {
+            Ok(self.0.pop_front())
+        }
+
+        fn next_byte_within(&mut self, _timeout: Duration) -> Result<Option<u8>, &'static str> {
+            Ok(self.0.pop_front())
+        }
+
+        fn unread(&mut self, byte: u8) {
+            self.0.push_front(byte);
+        }
+    }
+
+    // decodes events until the bytes run out, the same way Term does
+    fn decode_all(bytes: &[u8]) -> Vec<Option<Event>> {
+        let mut source = Bytes(bytes.iter().copied().collect());
+        let mut events = Vec::new();
+
+        while let Some(first) = source.0.pop_front() {
+            events.push(decode_event(first, &mut source).unwrap());
+        }
+
+        events
+    }
+
+    fn key(code: KeyCode, modifiers: Modifiers) -> Option<Event> {
+        Some(Event::Key(Key::with_modifiers(code, modifiers)))
+    }
+
+    #[test]
+    fn keys() {
+        let ctrl_alt = Modifiers {shift: false, ctrl: true, alt: true};
+
+        let cases: &[(&[u8], Option<Event>)] = &[
+            (b"a", key(KeyCode::Char('a'), Modifiers::NONE)),
+            (b"A", key(KeyCode::Char('A'), Modifiers::NONE)),
+            (b"\x01", key(KeyCode::Char('a'), Modifiers::CTRL)),
+            (b"\x03", key(KeyCode::Char('c'), Modifiers::CTRL)),
+            (b"\x00", key(KeyCode::Char(' '), Modifiers::CTRL)),
+            (b"\x1c", key(KeyCode::Char('\\'), Modifiers::CTRL)),
+            (b"\r", key(KeyCode::Enter, Modifiers::NONE)),
+            (b"\t", key(KeyCode::Tab, Modifiers::NONE)),
+            (b"\x7f", key(KeyCode::Backspace, Modifiers::NONE)),
+            (b"\x1b", key(KeyCode::Esc, Modifiers::NONE)),
+            (b"\x1b\x1b", key(KeyCode::Esc, Modifiers::ALT)),
+            (b"\x1bx", key(KeyCode::Char('x'), Modifiers::ALT)),
+            (b"\x1b\x01", key(KeyCode::Char('a'), ctrl_alt)),
+            (b"\x1b[A", key(KeyCode::Up, Modifiers::NONE)),
+            (b"\x1b[B", key(KeyCode::Down, Modifiers::NONE)),
+            (b"\x1b[1;5A", key(KeyCode::Up, Modifiers::CTRL)),
+            (b"\x1b[1;2D", key(KeyCode::Left, Modifiers::SHIFT)),
+            (b"\x1b[1;3C", key(KeyCode::Right, Modifiers::ALT)),
+            (b"\x1b[1;7H", key(KeyCode::Home, ctrl_alt)),
+            (b"\x1b[F", key(KeyCode::End, Modifiers::NONE)),
+            (b"\x1bOP", key(KeyCode::F(1), Modifiers::NONE)),
+            (b"\x1bOS", key(KeyCode::F(4), Modifiers::NONE)),
+            (b"\x1bOA", key(KeyCode::Up, Modifiers::NONE)),
+            (b"\x1bOM", key(KeyCode::Enter, Modifiers::NONE)),
+            (b"\x1b[1;5P", key(KeyCode::F(1), Modifiers::CTRL)),
+            (b"\x1b[15~", key(KeyCode::F(5), Modifiers::NONE)),
+            (b"\x1b[21~", key(KeyCode::F(10), Modifiers::NONE)),
+            (b"\x1b[24~", key(KeyCode::F(12), Modifiers::NONE)),
+            (b"\x1b[2~", key(KeyCode::Insert, Modifiers::NONE)),
+            (b"\x1b[3;5~", key(KeyCode::Delete, Modifiers::CTRL)),
+            (b"\x1b[1~", key(KeyCode::Home, Modifiers::NONE)),
+            (b"\x1b[4~", key(KeyCode::End, Modifiers::NONE)),
+            (b"\x1b[5~", key(KeyCode::PageUp, Modifiers::NONE)),
+            (b"\x1b[6;2~", key(KeyCode::PageDown, Modifiers::SHIFT)),
+            (b"\x1b[Z", key(KeyCode::BackTab, Modifiers::SHIFT)),
+            (b"\x1b[99~", None),
+            (b"\x1b[1;5X", None)
+        ];
+
+        for (bytes, expected) in cases {
+            assert_eq!(decode_all(bytes), vec![expected.clone()], "decoding {:?}", bytes.escape_ascii().to_string());
+        }
+    }
+
+    #[test]
+    fn cut_off_sequences() {
+        // nothing came after the O, so it was alt + O
+        assert_eq!(decode_all(b"\x1bO"), vec![key(KeyCode::Char('O'), Modifiers::ALT)]);
+
+        // a csi sequence without its final byte isnt a key
+        assert_eq!(decode_all(b"\x1b[1;5"), vec![None]);
+    }
+
+    #[test]
+    fn keys_back_to_back() {
+        assert_eq!(decode_all(b"\x1b[Ax\x1bOPq\r"), vec![
+            key(KeyCode::Up, Modifiers::NONE),
+            key(KeyCode::Char('x'), Modifiers::NONE),
+            key(KeyCode::F(1), Modifiers::NONE),
+            key(KeyCode::Char('q'), Modifiers::NONE),
+            key(KeyCode::Enter, Modifiers::NONE)
+        ]);
+    }
+
+    #[test]
+    fn unsupported_sequences_dont_eat_the_next_key() {
+        assert_eq!(decode_all(b"\x1b[99~a"), vec![None, key(KeyCode::Char('a'), Modifiers::NONE)]);
+    }
+
+    fn chars(bytes: &[u8]) -> Vec<char> {
+        let mut source = Bytes(bytes.iter().copied().collect());
+        let mut chars = Vec::new();
+
+        while let Some(first) = source.0.pop_front() {
+            chars.push(decode_utf8(first, &mut source).unwrap());
+        }
+
+        chars
+    }
+
+    #[test]
+    fn utf8() {
+        const INVALID: char = char::REPLACEMENT_CHARACTER;
+
+        let cases: &[(&[u8], &[char])] = &[
+            (b"a", &['a']),
+            (b"\xc3\xa9", &['é']),
+            (b"\xe2\x82\xac", &['€']),
+            (b"\xf0\x9f\x98\x80", &['😀']),
+            (b"\xc3\xa9\xc3\xa8", &['é', 'è']),
+
+            // bytes that never start a character
+            (b"\xff", &[INVALID]),
+            (b"\x80", &[INVALID]),
+            (b"\xf5\x80\x80\x80", &[INVALID, INVALID, INVALID, INVALID]),
+
+            // overlong encodings and surrogates
+            (b"\xc0\xaf", &[INVALID, INVALID]),
+            (b"\xe0\x80\xaf", &[INVALID]),
+            (b"\xed\xa0\x80", &[INVALID]),
+
+            // a byte that cant continue the character is read on its own
+            (b"\xc3a", &[INVALID, 'a']),
+            (b"\xe2\x82\xc3\xa9", &[INVALID, 'é']),
+
+            // the rest of the character never showed up
+            (b"\xe2\x82", &[INVALID])
+        ];
+
+        for (bytes, expected) in cases {
+            assert_eq!(chars(bytes), *expected, "decoding {:?}", bytes.escape_ascii().to_string());
+        }
+    }
+
+    #[test]
+    fn utf8_keys() {
+        assert_eq!(decode_all(b"\xc3\xa9"), vec![key(KeyCode::Char('é'), Modifiers::NONE)]);
+        assert_eq!(decode_all(b"\x1b\xc3\xa9"), vec![key(KeyCode::Char('é'), Modifiers::ALT)]);
+        assert_eq!(decode_all(b"\xff"), vec![key(KeyCode::Char(char::REPLACEMENT_CHARACTER), Modifiers::NONE)]);
+    }
+
+    fn mouse(kind: MouseKind, x: i64, y: i64, modifiers: Modifiers) -> Option<Event> {
+        Some(Event::Mouse(MouseEvent {kind, x, y, modifiers}))
+    }
+
+    #[test]
+    fn sgr_mouse_reports() {
+        let cases: &[(&[u8], Option<Event>)] = &[
+            (b"\x1b[<0;10;5M", mouse(MouseKind::Down(MouseButton::Left), 9, 4, Modifiers::NONE)),
+            (b"\x1b[<0;10;5m", mouse(MouseKind::Up(MouseButton::Left), 9, 4, Modifiers::NONE)),
+            (b"\x1b[<1;1;1M", mouse(MouseKind::Down(MouseButton::Middle), 0, 0, Modifiers::NONE)),
+            (b"\x1b[<2;300;200m", mouse(MouseKind::Up(MouseButton::Right), 299, 199, Modifiers::NONE)),
+            (b"\x1b[<32;3;4M", mouse(MouseKind::Drag(MouseButton::Left), 2, 3, Modifiers::NONE)),
+            (b"\x1b[<35;3;4M", mouse(MouseKind::Moved, 2, 3, Modifiers::NONE)),
+            (b"\x1b[<64;5;6M", mouse(MouseKind::ScrollUp, 4, 5, Modifiers::NONE)),
+            (b"\x1b[<65;5;6M", mouse(MouseKind::ScrollDown, 4, 5, Modifiers::NONE)),
+            (b"\x1b[<66;5;6M", mouse(MouseKind::ScrollLeft, 4, 5, Modifiers::NONE)),
+            (b"\x1b[<67;5;6M", mouse(MouseKind::ScrollRight, 4, 5, Modifiers::NONE)),
+            (b"\x1b[<4;2;2M", mouse(MouseKind::Down(MouseButton::Left), 1, 1, Modifiers::SHIFT)),
+            (b"\x1b[<8;2;2M", mouse(MouseKind::Down(MouseButton::Left), 1, 1, Modifiers::ALT)),
+            (b"\x1b[<18;2;2M", mouse(MouseKind::Down(MouseButton::Right), 1, 1, Modifiers::CTRL)),
+
+            // positions start at 1, and a report needs all three parameters
+            (b"\x1b[<0;0;5M", None),
+            (b"\x1b[<0;10M", None)
+        ];
+
+        for (bytes, expected) in cases {
+            assert_eq!(decode_all(bytes), vec![expected.clone()], "decoding {:?}", bytes.escape_ascii().to_string());
+        }
+    }
+
+    #[test]
+    fn x10_mouse_reports() {
+        let cases: &[(&[u8], Option<Event>)] = &[
+            // the button and position are offset by 32, so (9, 4) is sent as 42 and 37
+            (b"\x1b[M *%", mouse(MouseKind::Down(MouseButton::Left), 9, 4, Modifiers::NONE)),
+            (b"\x1b[M\"!!", mouse(MouseKind::Down(MouseButton::Right), 0, 0, Modifiers::NONE)),
+            (b"\x1b[M#*%", mouse(MouseKind::Up(MouseButton::Left), 9, 4, Modifiers::NONE)),
+            (b"\x1b[M`*%", mouse(MouseKind::ScrollUp, 9, 4, Modifiers::NONE)),
+            (b"\x1b[M@*%", mouse(MouseKind::Drag(MouseButton::Left), 9, 4, Modifiers::NONE)),
+            (b"\x1b[M0*%", mouse(MouseKind::Down(MouseButton::Left), 9, 4, Modifiers::CTRL)),
+
+            // bytes below 32 and a position of 0 arent valid
+            (b"\x1b[M\x1f*%", None),
+            (b"\x1b[M  %", None)
+        ];
+
+        for (bytes, expected) in cases {
+            assert_eq!(decode_all(bytes), vec![expected.clone()], "decoding {:?}", bytes.escape_ascii().to_string());
+        }
+    }
+
+    #[test]
+    fn cut_off_x10_mouse_report() {
+        assert_eq!(decode_all(b"\x1b[M *"), vec![None]);
+    }
+
+    #[test]
+    fn mouse_reports_between_keys() {
+        assert_eq!(decode_all(b"a\x1b[<0;10;5Mb"), vec![
+            key(KeyCode::Char('a'), Modifiers::NONE),
+            mouse(MouseKind::Down(MouseButton::Left), 9, 4, Modifiers::NONE),
+            key(KeyCode::Char('b'), Modifiers::NONE)
+        ]);
+    }
+
+    fn paste(text: &str) -> Option<Event> {
+        Some(Event::Paste(text.to_string()))
+    }
+
+    #[test]
+    fn pastes() {
+        assert_eq!(decode_all(b"\x1b[200~hello\x1b[201~"), vec![paste("hello")]);
+        assert_eq!(decode_all(b"\x1b[200~\x1b[201~"), vec![paste("")]);
+
+        // escape sequences and newlines inside a paste are text, not keys
+        assert_eq!(decode_all(b"\x1b[200~a\x1b[Ab\r\nc\x1b[201~"), vec![paste("a\x1b[Ab\r\nc")]);
+        assert_eq!(decode_all("\x1b[200~\u{e9}\u{4e16}\x1b[201~".as_bytes()), vec![paste("\u{e9}\u{4e16}")]);
+
+        // keys after the end of the paste are keys again
+        assert_eq!(decode_all(b"\x1b[200~hi\x1b[201~q"), vec![paste("hi"), key(KeyCode::Char('q'), Modifiers::NONE)]);
+    }
+
+    #[test]
+    fn cut_off_pastes() {
+        // the end never showed up, so everything after the start is the paste
+        assert_eq!(decode_all(b"\x1b[200~hello"), vec![paste("hello")]);
+
+        // only part of the end showed up, so it is part of the text
+        assert_eq!(decode_all(b"\x1b[200~hello\x1b[20"), vec![paste("hello\x1b[20")]);
+    }
+
+    #[test]
+    fn focus_reports() {
+        assert_eq!(decode_all(b"\x1b[I\x1b[O"), vec![Some(Event::FocusGained), Some(Event::FocusLost)]);
+
+        // with parameters they arent focus reports
+        assert_eq!(decode_all(b"\x1b[1I"), vec![None]);
+        assert_eq!(decode_all(b"\x1b[2O"), vec![None]);
+    }
+}
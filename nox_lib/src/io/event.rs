@@ -0,0 +1,39 @@
+use std::time::{Duration, Instant};
+
+use super::key::Key;
+use super::mouse::MouseEvent;
+
+/// Something that happened in the terminal
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Event {
+    Key(Key),
+    Mouse(MouseEvent),
+    /// The terminal was resized to the given width and height
+    Resize(i64, i64),
+    /// Text that was pasted while bracketed paste was enabled
+    Paste(String),
+    /// The terminal window gained focus while focus reporting was enabled
+    FocusGained,
+    /// The terminal window lost focus while focus reporting was enabled
+    FocusLost,
+    /// A timer went off
+    Tick(TimerId)
+}
+
+/// Identifies a timer added to a Term
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TimerId(u64);
+
+pub(super) struct Timer {
+    pub(super) id: TimerId,
+    pub(super) deadline: Instant,
+
+    // None for timers that only go off once
+    pub(super) interval: Option<Duration>
+}
+
+impl Timer {
+    pub(super) fn new(id: u64, delay: Duration, interval: Option<Duration>) -> Self {
+        Self {id: TimerId(id), deadline: Instant::now() + delay, interval}
+    }
+}
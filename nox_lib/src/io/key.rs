@@ -0,0 +1,138 @@
+/// A key that was pressed
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: Modifiers
+}
+
+/// Which key was pressed, letters keep their case so shift isnt reported for them
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum KeyCode {
+    Char(char),
+    Enter,
+    Tab,
+    /// Shift + Tab
+    BackTab,
+    Backspace,
+    Esc,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    /// A function key, F(1) to F(12)
+    F(u8)
+}
+
+/// The modifier keys held down while a key was pressed
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool
+}
+
+impl Key {
+    pub fn new(code: KeyCode) -> Self {
+        Self {code, modifiers: Modifiers::NONE}
+    }
+
+    pub fn with_modifiers(code: KeyCode, modifiers: Modifiers) -> Self {
+        Self {code, modifiers}
+    }
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {shift: false, ctrl: false, alt: false};
+    pub const SHIFT: Modifiers = Modifiers {shift: true, ctrl: false, alt: false};
+    pub const CTRL: Modifiers = Modifiers {shift: false, ctrl: true, alt: false};
+    pub const ALT: Modifiers = Modifiers {shift: false, ctrl: false, alt: true};
+
+    // xterm sends modifiers as 1 + a bit mask of shift = 1, alt = 2 and ctrl = 4
+    pub(super) fn from_xterm(param: u16) -> Self {
+        let mask = param.saturating_sub(1);
+
+        Self {
+            shift: mask & 1 != 0,
+            alt: mask & 2 != 0,
+            ctrl: mask & 4 != 0
+        }
+    }
+}
+
+
+
+/*
+    ##### DECODING #####
+*/
+
+
+
+// keys are sent by the terminal as either a single byte or an escape sequence
+// | plain key | | ctrl + key | | alt + key | |   csi sequence   | | ss3 sequence |
+//      a          \x01          \x1b a       \x1b [ 1 ; 5 A       \x1b O P
+
+/// Decodes a key that is sent as a single byte
+pub(super) fn byte_key(byte: u8) -> Key {
+    match byte {
+        b'\r' | b'\n' => Key::new(KeyCode::Enter),
+        b'\t' => Key::new(KeyCode::Tab),
+        0x7f | 0x08 => Key::new(KeyCode::Backspace),
+        0x1b => Key::new(KeyCode::Esc),
+        0x00 => Key::with_modifiers(KeyCode::Char(' '), Modifiers::CTRL),
+        0x01..=0x1a => Key::with_modifiers(KeyCode::Char((byte - 1 + b'a') as char), Modifiers::CTRL),
+        0x1c..=0x1f => Key::with_modifiers(KeyCode::Char((byte - 0x1c + b'\\') as char), Modifiers::CTRL),
+        _ => Key::new(KeyCode::Char(byte as char))
+    }
+}
+
+/// Decodes a key from the parameters and final byte of a csi sequence, None if it isnt a key
+pub(super) fn csi_key(params: &[u16], final_byte: u8) -> Option<Key> {
+    let modifier_param = params.get(1).copied().unwrap_or(1);
+
+    match final_byte {
+        b'~' => {
+            let code = match params.first().copied().unwrap_or(0) {
+                1 | 7 => KeyCode::Home,
+                2 => KeyCode::Insert,
+                3 => KeyCode::Delete,
+                4 | 8 => KeyCode::End,
+                5 => KeyCode::PageUp,
+                6 => KeyCode::PageDown,
+                number @ 11..=15 => KeyCode::F((number - 10) as u8),
+                number @ 17..=21 => KeyCode::F((number - 11) as u8),
+                number @ 23..=24 => KeyCode::F((number - 12) as u8),
+                _ => return None
+            };
+
+            Some(Key::with_modifiers(code, Modifiers::from_xterm(modifier_param)))
+        },
+        b'Z' => Some(Key::with_modifiers(KeyCode::BackTab, Modifiers::SHIFT)),
+        byte => ss3_key(byte, modifier_param)
+    }
+}
+
+/// Decodes keys that end in the same letter in both csi and ss3 sequences
+pub(super) fn ss3_key(byte: u8, modifier_param: u16) -> Option<Key> {
+    let code = match byte {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'P' => KeyCode::F(1),
+        b'Q' => KeyCode::F(2),
+        b'R' => KeyCode::F(3),
+        b'S' => KeyCode::F(4),
+        b'M' => KeyCode::Enter, // keypad enter in application mode
+        _ => return None
+    };
+
+    Some(Key::with_modifiers(code, Modifiers::from_xterm(modifier_param)))
+}
@@ -1,4 +1,23 @@
 /// A module for terminal io features
 mod term;
 
-pub use term::Term;
\ No newline at end of file
+/// A module for decoding the bytes sent by the terminal
+mod decode;
+
+/// A module for the events the terminal sends
+mod event;
+
+/// A module for key presses
+mod key;
+
+/// A module for mouse events
+mod mouse;
+
+/// A module for putting the terminal back the way it was
+mod session;
+
+pub use term::Term;
+pub use event::{Event, TimerId};
+pub use key::{Key, KeyCode, Modifiers};
+pub use mouse::{MouseButton, MouseEvent, MouseKind};
+pub use session::Session;
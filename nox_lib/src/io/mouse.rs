@@ -0,0 +1,118 @@
+use super::key::Modifiers;
+
+/// Something the mouse did, x and y are the cell it happened on, starting at 0 in the top left corner of the terminal,
+/// Gui::mouse_event moves them to the cells of a gui that isnt drawn in the top left corner
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct MouseEvent {
+    pub kind: MouseKind,
+    pub x: i64,
+    pub y: i64,
+    pub modifiers: Modifiers
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MouseKind {
+    Down(MouseButton),
+    /// X10 reports dont say which button was released, so they always report the left button
+    Up(MouseButton),
+    /// The mouse moved with a button held down
+    Drag(MouseButton),
+    /// The mouse moved without any buttons held down
+    Moved,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right
+}
+
+
+
+/*
+    ##### DECODING #####
+*/
+
+
+
+// the terminal sends mouse reports as either sgr (1006) or x10 sequences
+// |        sgr        | |           x10           |
+// \x1b[<bbb;xxx;yyyM     \x1b[M  b+32  x+32  y+32
+// sgr ends with m instead of M when a button is released, x10 uses button 3 for that
+//
+// the button byte is made of
+// | button 0-3 | | shift 4 | | alt 8 | | ctrl 16 | | motion 32 | | wheel 64 |
+
+/// Decodes the parameters of an sgr mouse report, None if they arent valid
+pub(super) fn sgr_mouse(params: &[u16], pressed: bool) -> Option<MouseEvent> {
+    if params.len() < 3 {
+        return None;
+    }
+
+    let mut event = decode_button(params[0], params[1], params[2])?;
+
+    if !pressed {
+        if let MouseKind::Down(button) = event.kind {
+            event.kind = MouseKind::Up(button);
+        }
+    }
+
+    Some(event)
+}
+
+/// Decodes the three bytes after an x10 mouse report, None if they arent valid
+pub(super) fn x10_mouse(button: u8, x: u8, y: u8) -> Option<MouseEvent> {
+    let button = button.checked_sub(32)? as u16;
+    let x = x.checked_sub(32)? as u16;
+    let y = y.checked_sub(32)? as u16;
+
+    decode_button(button, x, y)
+}
+
+// turns the button code and the 1 based position into an event
+fn decode_button(code: u16, x: u16, y: u16) -> Option<MouseEvent> {
+    let modifiers = Modifiers {
+        shift: code & 4 != 0,
+        alt: code & 8 != 0,
+        ctrl: code & 16 != 0
+    };
+
+    let button = match code & 0b11 {
+        0 => Some(MouseButton::Left),
+        1 => Some(MouseButton::Middle),
+        2 => Some(MouseButton::Right),
+        _ => None
+    };
+
+    let kind = if code & 64 != 0 {
+        match code & 0b11 {
+            0 => MouseKind::ScrollUp,
+            1 => MouseKind::ScrollDown,
+            2 => MouseKind::ScrollLeft,
+            _ => MouseKind::ScrollRight
+        }
+    }
+    else if code & 32 != 0 {
+        match button {
+            Some(button) => MouseKind::Drag(button),
+            None => MouseKind::Moved
+        }
+    }
+    else {
+        match button {
+            Some(button) => MouseKind::Down(button),
+            None => MouseKind::Up(MouseButton::Left) // only x10 uses button 3, for releases
+        }
+    };
+
+    if x == 0 || y == 0 {
+        return None;
+    }
+
+    Some(MouseEvent {kind, x: x as i64 - 1, y: y as i64 - 1, modifiers})
+}
@@ -0,0 +1,215 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::{Mutex, Once};
+
+use super::Term;
+
+
+
+/*
+    ##### UNIX PORTION #####
+*/
+
+
+
+#[cfg(target_family = "unix")]
+use libc::{termios, tcsetattr, STDIN_FILENO, STDOUT_FILENO};
+
+#[cfg(target_family = "unix")]
+use libc::{sigaction, sigemptyset, sighandler_t, signal, raise, write, c_int, c_void, SIG_DFL, SIGHUP, SIGINT, SIGQUIT, SIGTERM};
+
+// resets the attributes and scroll region, shows the cursor and turns off mouse, paste and focus reporting
+#[cfg(target_family = "unix")]
+const RESET_SEQUENCE: &[u8] = b"\x1b[m\x1b[r\x1b[?25h\x1b[?1006l\x1b[?1003l\x1b[?1002l\x1b[?1000l\x1b[?2004l\x1b[?1004l";
+
+#[cfg(target_family = "unix")]
+const LEAVE_ALTERNATE_SCREEN: &[u8] = b"\x1b[?1049l";
+
+// what has to be undone when the session ends, kept outside of the session so the panic hook can get to it
+#[cfg(target_family = "unix")]
+struct SavedState {
+    termios_settings: termios,
+    alternate_screen: bool
+}
+
+#[cfg(target_family = "unix")]
+static SAVED_STATE: Mutex<Option<SavedState>> = Mutex::new(None);
+
+#[cfg(target_family = "unix")]
+static PANIC_HOOK: Once = Once::new();
+
+// a copy of the saved settings for the signal handler, which cant lock the mutex,
+// null when there is no active session or when the terminal was already put back
+#[cfg(target_family = "unix")]
+static SIGNAL_SETTINGS: AtomicPtr<termios> = AtomicPtr::new(std::ptr::null_mut());
+
+#[cfg(target_family = "unix")]
+static SIGNAL_ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+
+#[cfg(target_family = "unix")]
+static SIGNAL_HANDLERS: Once = Once::new();
+
+/// Puts the terminal in the state a full screen app wants (raw input, hidden cursor and optionally the alternate screen)
+/// and puts it back the way it was when dropped or when the program panics (Unix Implementation)
+///
+/// Ctrl-c and the other signals that end a program still end it during a session, the terminal is put back first
+/// unless the program has its own handlers for them
+///
+/// Only one session can be active at a time
+#[cfg(target_family = "unix")]
+pub struct Session {
+    term: Term
+}
+
+#[cfg(target_family = "unix")]
+impl Session {
+    /// Enables raw input and hides the cursor
+    pub fn new() -> Result<Self, &'static str> {
+        Self::start(false)
+    }
+
+    /// Enables raw input, hides the cursor and switches to the alternate screen,
+    /// the contents of the normal screen come back when the session ends
+    pub fn with_alternate_screen() -> Result<Self, &'static str> {
+        Self::start(true)
+    }
+
+    fn start(alternate_screen: bool) -> Result<Self, &'static str> {
+        let mut saved_state = SAVED_STATE.lock().unwrap_or_else(|err| err.into_inner());
+
+        if saved_state.is_some() {
+            return Err("a terminal session is already active");
+        }
+
+        let mut term = Term::new()?;
+        term.save_state()?;
+
+        *saved_state = Some(SavedState {termios_settings: term.saved_settings(), alternate_screen});
+        drop(saved_state);
+
+        SIGNAL_ALTERNATE_SCREEN.store(alternate_screen, Ordering::SeqCst);
+        SIGNAL_SETTINGS.store(Box::into_raw(Box::new(term.saved_settings())), Ordering::SeqCst);
+        SIGNAL_HANDLERS.call_once(install_signal_handlers);
+
+        PANIC_HOOK.call_once(|| {
+            let previous_hook = std::panic::take_hook();
+
+            std::panic::set_hook(Box::new(move |info| {
+                restore_terminal();
+                previous_hook(info);
+            }));
+        });
+
+        if let Err(err) = term.enable_raw_input() {
+            restore_terminal();
+            return Err(err);
+        }
+
+        let session = Self {term};
+
+        let mut stdout = std::io::stdout();
+
+        if alternate_screen {
+            stdout.write_all(b"\x1b[?1049h").map_err(|_| "failed to write to stdout")?;
+        }
+
+        stdout.write_all(b"\x1b[?25l").map_err(|_| "failed to write to stdout")?;
+        stdout.flush().map_err(|_| "failed to write to stdout")?;
+
+        Ok(session)
+    }
+
+    pub fn term(&self) -> &Term {
+        &self.term
+    }
+
+    pub fn term_mut(&mut self) -> &mut Term {
+        &mut self.term
+    }
+}
+
+#[cfg(target_family = "unix")]
+impl Drop for Session {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+// undoes everything the active session did, does nothing if there is no active session
+#[cfg(target_family = "unix")]
+fn restore_terminal() {
+    let signal_settings = SIGNAL_SETTINGS.swap(std::ptr::null_mut(), Ordering::SeqCst);
+
+    if !signal_settings.is_null() {
+        drop(unsafe {Box::from_raw(signal_settings)});
+    }
+
+    // try_lock so a panic while the state is locked cant deadlock the panic hook
+    let saved_state = match SAVED_STATE.try_lock() {
+        Ok(mut saved_state) => saved_state.take(),
+        Err(std::sync::TryLockError::Poisoned(err)) => err.into_inner().take(),
+        Err(std::sync::TryLockError::WouldBlock) => return
+    };
+
+    let saved_state = match saved_state {
+        Some(saved_state) => saved_state,
+        None => return
+    };
+
+    unsafe {
+        tcsetattr(STDIN_FILENO, 0, &saved_state.termios_settings);
+    }
+
+    let mut stdout = std::io::stdout();
+
+    // the errors are ignored since there is nothing left to do if stdout is gone,
+    // scroll regions, mouse, paste and focus reporting are turned off in case they were turned on during the session
+    let _ = stdout.write_all(RESET_SEQUENCE);
+
+    if saved_state.alternate_screen {
+        let _ = stdout.write_all(LEAVE_ALTERNATE_SCREEN);
+    }
+
+    let _ = stdout.flush();
+}
+
+// makes the signals that end the program put the terminal back first,
+// signals the program already handles or ignores are left alone
+#[cfg(target_family = "unix")]
+fn install_signal_handlers() {
+    extern "C" fn handle_termination(signum: c_int) {
+        // only async signal safe calls are allowed in here, the program ends right after so nothing is freed
+        unsafe {
+            let settings = SIGNAL_SETTINGS.swap(std::ptr::null_mut(), Ordering::SeqCst);
+
+            if !settings.is_null() {
+                tcsetattr(STDIN_FILENO, 0, settings);
+                write(STDOUT_FILENO, RESET_SEQUENCE.as_ptr() as *const c_void, RESET_SEQUENCE.len());
+
+                if SIGNAL_ALTERNATE_SCREEN.load(Ordering::SeqCst) {
+                    write(STDOUT_FILENO, LEAVE_ALTERNATE_SCREEN.as_ptr() as *const c_void, LEAVE_ALTERNATE_SCREEN.len());
+                }
+            }
+
+            // the default action ends the program the same way the signal would have without the handler
+            signal(signum, SIG_DFL);
+            raise(signum);
+        }
+    }
+
+    for signum in [SIGHUP, SIGINT, SIGQUIT, SIGTERM] {
+        unsafe {
+            let mut previous: sigaction = std::mem::zeroed();
+
+            if sigaction(signum, std::ptr::null(), &mut previous) == -1 || previous.sa_sigaction != SIG_DFL {
+                continue;
+            }
+
+            let mut action: sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle_termination as extern "C" fn(c_int) as sighandler_t;
+            sigemptyset(&mut action.sa_mask);
+
+            sigaction(signum, &action, std::ptr::null_mut());
+        }
+    }
+}
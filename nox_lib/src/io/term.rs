@@ -1,4 +1,12 @@
-use std::time::Duration;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use super::decode::{decode_event, decode_utf8, ByteSource};
+use super::event::{Event, Timer, TimerId};
+use super::key::Key;
 
 
 
@@ -9,14 +17,50 @@ use std::time::Duration;
 
 
 #[cfg(target_family = "unix")]
-use libc::{termios, tcgetattr, tcsetattr, STDIN_FILENO, ECHO, ICANON, select, timeval, read, FD_SET, FD_ZERO, fd_set, c_void};
+use libc::{termios, tcgetattr, tcsetattr, STDIN_FILENO, ECHO, ICANON, select, timeval, read, FD_SET, FD_ZERO, FD_ISSET, fd_set, c_void};
+
+#[cfg(target_family = "unix")]
+use libc::{ioctl, winsize, TIOCGWINSZ, STDOUT_FILENO, STDERR_FILENO, pipe, fcntl, F_GETFL, F_SETFL, F_SETFD, O_NONBLOCK, FD_CLOEXEC, write, c_int};
+
+#[cfg(target_family = "unix")]
+use libc::{sigaction, sigemptyset, siginfo_t, sighandler_t, SIGWINCH, SA_RESTART, SA_SIGINFO, SIG_DFL, SIG_IGN};
+
+// the sigwinch handler writes a byte to this pipe so waiting for input wakes up when the terminal is resized,
+// -1 until the handler is installed
+#[cfg(target_family = "unix")]
+static RESIZE_PIPE_READ: AtomicI32 = AtomicI32::new(-1);
+
+#[cfg(target_family = "unix")]
+static RESIZE_PIPE_WRITE: AtomicI32 = AtomicI32::new(-1);
+
+#[cfg(target_family = "unix")]
+static RESIZE_HANDLER: Once = Once::new();
+
+// the sigwinch handler that was there before ours, it gets called after ours so the host app still hears about resizes
+#[cfg(target_family = "unix")]
+static PREVIOUS_RESIZE_HANDLER: AtomicUsize = AtomicUsize::new(SIG_DFL);
+
+#[cfg(target_family = "unix")]
+static PREVIOUS_RESIZE_HANDLER_SIGINFO: AtomicBool = AtomicBool::new(false);
 
 /// Struct representing the terminal (Unix Implementation)
 #[cfg(target_family = "unix")]
 pub struct Term {
     termios_settings: termios,
     echo: bool,
-    canon: bool
+    canon: bool,
+
+    // bytes that were read from stdin but not used yet
+    input: VecDeque<u8>,
+
+    // how long to wait for the rest of an escape sequence before treating it as the escape key
+    escape_timeout: Duration,
+
+    // set when a sigwinch was noticed but the resize event wasnt returned yet
+    resized: bool,
+
+    timers: Vec<Timer>,
+    next_timer_id: u64
 }
 
 #[cfg(target_family = "unix")]
@@ -41,7 +85,34 @@ impl Term {
             canon = termios_settings.c_lflag & ICANON != 0;
         }
 
-        Ok(Self {termios_settings, echo, canon})
+        RESIZE_HANDLER.call_once(install_resize_handler);
+
+        Ok(Self {
+            termios_settings,
+            echo,
+            canon,
+            input: VecDeque::new(),
+            escape_timeout: Duration::from_millis(50),
+            resized: false,
+            timers: Vec::new(),
+            next_timer_id: 0
+        })
+    }
+
+    /// Returns the size of the terminal as (width, height) in cells
+    pub fn size() -> Result<(i64, i64), &'static str> {
+        // stdout is usually the terminal being drawn to, but it might be redirected while the others arent
+        for fd in [STDOUT_FILENO, STDIN_FILENO, STDERR_FILENO] {
+            unsafe {
+                let mut size: winsize = std::mem::zeroed();
+
+                if ioctl(fd, TIOCGWINSZ, &mut size) == 0 && size.ws_col > 0 && size.ws_row > 0 {
+                    return Ok((size.ws_col as i64, size.ws_row as i64));
+                }
+            }
+        }
+
+        Err("failed to get the terminal size")
     }
 
     /// Saves the current termios settings
@@ -57,6 +128,11 @@ impl Term {
         Ok(())
     }
 
+    /// The termios settings from the last call to save_state
+    pub(super) fn saved_settings(&self) -> termios {
+        self.termios_settings
+    }
+
     /// Restores the saved termios settings
     pub fn restore_state(&mut self) -> Result<(), &'static str> {
         unsafe {
@@ -84,9 +160,9 @@ impl Term {
                 return Err("failed to get termios settings");
             }
 
-            term_settings.c_lflag &= ECHO;
+            term_settings.c_lflag |= ECHO;
 
-            err = tcsetattr(STDIN_FILENO, 0, &mut term_settings);
+            err = tcsetattr(STDIN_FILENO, 0, &term_settings);
 
             if err == -1 {
                 return Err("failed to set termios settings");
@@ -111,7 +187,7 @@ impl Term {
 
             term_settings.c_lflag &= !ECHO;
 
-            err = tcsetattr(STDIN_FILENO, 0, &mut term_settings);
+            err = tcsetattr(STDIN_FILENO, 0, &term_settings);
 
             if err == -1 {
                 return Err("failed to set termios settings");
@@ -134,9 +210,9 @@ impl Term {
                 return Err("failed to get termios settings");
             }
 
-            term_settings.c_lflag &= ICANON;
+            term_settings.c_lflag |= ICANON;
 
-            err = tcsetattr(STDIN_FILENO, 0, &mut term_settings);
+            err = tcsetattr(STDIN_FILENO, 0, &term_settings);
 
             if err == -1 {
                 return Err("failed to set termios settings");
@@ -161,7 +237,7 @@ impl Term {
 
             term_settings.c_lflag &= !ICANON;
 
-            err = tcsetattr(STDIN_FILENO, 0, &mut term_settings);
+            err = tcsetattr(STDIN_FILENO, 0, &term_settings);
 
             if err == -1 {
                 return Err("failed to set termios settings");
@@ -187,13 +263,194 @@ impl Term {
         Ok(())
     }
 
-    /// retrieves one character from stdin unbuffered, without echo
+    /// Sets how long read_key waits for the rest of an escape sequence before reporting the escape key, defaults to 50ms
+    pub fn set_escape_timeout(&mut self, timeout: Duration) {
+        self.escape_timeout = timeout;
+    }
+
+    /// retrieves one character from stdin unbuffered, without echo,
+    /// multi byte utf-8 characters are put together and invalid utf-8 is returned as U+FFFD
     pub fn getch(&mut self, dur: Duration) -> Result<Option<char>, &'static str> {
+        self.with_raw_input(|term| {
+            match term.read_byte(dur)? {
+                None => Ok(None),
+                Some(byte) => Ok(Some(decode_utf8(byte, term)?))
+            }
+        })
+    }
+
+    /// retrieves one key press from stdin unbuffered, without echo,
+    /// escape sequences for arrows, function keys and the like are turned into a single key,
+    /// utf-8 is handled the same way as getch
+    ///
+    /// Other events are skipped, use read_event to get them as well
+    pub fn read_key(&mut self, dur: Duration) -> Result<Option<Key>, &'static str> {
+        // skipped events dont restart the wait
+        let until = Instant::now() + dur;
+
+        loop {
+            match self.next_event(Some(until))? {
+                None => return Ok(None),
+                Some(Event::Key(key)) => return Ok(Some(key)),
+                Some(_) => continue
+            }
+        }
+    }
+
+    /// retrieves the next event, waiting up to dur for one to happen,
+    /// timers that go off while waiting are returned as tick events
+    pub fn read_event(&mut self, dur: Duration) -> Result<Option<Event>, &'static str> {
+        self.next_event(Some(Instant::now() + dur))
+    }
+
+    /// Waits without using the cpu until input arrives or a timer goes off and returns the event
+    pub fn wait_event(&mut self) -> Result<Event, &'static str> {
+        loop {
+            if let Some(event) = self.next_event(None)? {
+                return Ok(event);
+            }
+        }
+    }
+
+    // waits for an event until the given time, or forever if there isnt one
+    fn next_event(&mut self, until: Option<Instant>) -> Result<Option<Event>, &'static str> {
+        self.with_raw_input(|term| {
+            loop {
+                if term.resized {
+                    term.resized = false;
+
+                    // a terminal that doesnt know its size cant report a useful resize
+                    if let Ok((width, height)) = Term::size() {
+                        return Ok(Some(Event::Resize(width, height)));
+                    }
+                }
+
+                if let Some(id) = fire_timer(&mut term.timers, Instant::now()) {
+                    return Ok(Some(Event::Tick(id)));
+                }
+
+                // wake up for whichever comes first, the end of the wait or the next timer
+                let deadline = match (until, next_deadline(&term.timers)) {
+                    (Some(until), Some(timer)) => Some(until.min(timer)),
+                    (until, timer) => until.or(timer)
+                };
+
+                let timeout = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+                let has_input = term.wait_for_input(timeout)?;
+
+                if term.resized {
+                    continue;
+                }
+
+                if has_input {
+                    let first = term.input.pop_front().unwrap();
+
+                    // sequences that arent supported are skipped, so try again with whatever comes after them
+                    if let Some(event) = decode_event(first, term)? {
+                        return Ok(Some(event));
+                    }
+                }
+                else if until.is_some_and(|until| Instant::now() >= until) {
+                    return Ok(None);
+                }
+            }
+        })
+    }
+
+    /// Makes the terminal report mouse clicks, drags, movement and the scroll wheel
+    pub fn enable_mouse(&mut self) -> Result<(), &'static str> {
+        write_stdout(b"\x1b[?1000h\x1b[?1002h\x1b[?1003h\x1b[?1006h")
+    }
+
+    /// Stops the terminal from reporting the mouse
+    pub fn disable_mouse(&mut self) -> Result<(), &'static str> {
+        write_stdout(b"\x1b[?1006l\x1b[?1003l\x1b[?1002l\x1b[?1000l")
+    }
+
+    /// Adds a timer that sends a tick event every interval
+    pub fn add_timer(&mut self, interval: Duration) -> TimerId {
+        self.next_timer_id += 1;
+        let timer = Timer::new(self.next_timer_id, interval, Some(interval));
+        let id = timer.id;
+
+        self.timers.push(timer);
+        id
+    }
+
+    /// Adds a timer that sends a single tick event after the delay
+    pub fn add_timeout(&mut self, delay: Duration) -> TimerId {
+        self.next_timer_id += 1;
+        let timer = Timer::new(self.next_timer_id, delay, None);
+        let id = timer.id;
+
+        self.timers.push(timer);
+        id
+    }
+
+    /// Removes a timer, does nothing if it already went off or was removed
+    pub fn remove_timer(&mut self, id: TimerId) {
+        self.timers.retain(|timer| timer.id != id);
+    }
+
+    /// Makes the terminal mark pasted text so it is returned as a single paste event
+    pub fn enable_bracketed_paste(&mut self) -> Result<(), &'static str> {
+        write_stdout(b"\x1b[?2004h")
+    }
+
+    pub fn disable_bracketed_paste(&mut self) -> Result<(), &'static str> {
+        write_stdout(b"\x1b[?2004l")
+    }
+
+    /// Makes the terminal report when its window gains or loses focus
+    pub fn enable_focus_reporting(&mut self) -> Result<(), &'static str> {
+        write_stdout(b"\x1b[?1004h")
+    }
+
+    pub fn disable_focus_reporting(&mut self) -> Result<(), &'static str> {
+        write_stdout(b"\x1b[?1004l")
+    }
+
+    /// Asks the terminal where the cursor is and returns it as (x, y), starting at 0 in the top left corner,
+    /// input that arrives before the answer is kept for the next read
+    pub fn cursor_position(&mut self) -> Result<(i64, i64), &'static str> {
+        // echo is turned off first so the answer doesnt show up on the screen
+        self.with_raw_input(|term| {
+            write_stdout(b"\x1b[6n")?;
+
+            // the answer looks like \x1b[yyy;xxxR, anything before it was typed while waiting
+            let mut bytes = Vec::new();
+            let deadline = Instant::now() + Duration::from_secs(1);
+
+            let position = loop {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+
+                match term.read_byte(timeout)? {
+                    None => break None,
+                    Some(byte) => bytes.push(byte)
+                }
+
+                if let Some(position) = cursor_report(&mut bytes) {
+                    break Some(position);
+                }
+            };
+
+            for &byte in bytes.iter().rev() {
+                term.input.push_front(byte);
+            }
+
+            position.ok_or("the terminal didnt report the cursor position")
+        })
+    }
+
+    // runs f with echo disabled and unbuffered input, then puts the settings back
+    fn with_raw_input<T, F>(&mut self, f: F) -> Result<T, &'static str>
+    where
+        F: FnOnce(&mut Self) -> Result<T, &'static str>
+    {
         let echo = self.echo;
         let canon = self.canon;
 
-        let mut result = Ok(None);
-
         if canon {
             self.enable_raw_mode()?;
         }
@@ -202,38 +459,302 @@ impl Term {
             self.disable_echo()?;
         }
 
-        
+        let result = f(self);
+
+        if canon {
+            self.disable_raw_mode()?;
+        }
+
+        if echo {
+            self.enable_echo()?;
+        }
+
+        result
+    }
+
+    // returns the next byte from stdin, waiting up to dur for one to show up
+    fn read_byte(&mut self, dur: Duration) -> Result<Option<u8>, &'static str> {
+        let deadline = Instant::now() + dur;
+
+        // a resize can wake the wait up in the middle of an escape sequence, so keep waiting for the rest of it,
+        // the resize event is sent once the sequence is done
+        while !self.wait_for_input(Some(deadline.saturating_duration_since(Instant::now())))? {
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+        }
+
+        Ok(self.input.pop_front())
+    }
+
+    // waits until there is input to use, or until the timeout runs out (forever if there isnt one),
+    // returns false if there is still nothing to use
+    fn wait_for_input(&mut self, timeout: Option<Duration>) -> Result<bool, &'static str> {
+        if !self.input.is_empty() {
+            return Ok(true);
+        }
+
+        let resize_pipe = RESIZE_PIPE_READ.load(Ordering::Relaxed);
+
         unsafe {
             let mut read_fds: fd_set = std::mem::zeroed();
             FD_ZERO(&mut read_fds);
             FD_SET(STDIN_FILENO, &mut read_fds);
+
+            if resize_pipe >= 0 {
+                FD_SET(resize_pipe, &mut read_fds);
+            }
             
-            let mut tv = timeval {tv_sec: 0, tv_usec: dur.as_micros() as i64};
+            let mut tv: timeval = std::mem::zeroed();
+            let tv_ptr = match timeout {
+                Some(dur) => {
+                    tv.tv_sec = dur.as_secs() as _;
+                    tv.tv_usec = dur.subsec_micros() as _;
+                    &mut tv as *mut timeval
+                },
+                None => std::ptr::null_mut()
+            };
+
+            let has_input = select(STDIN_FILENO.max(resize_pipe) + 1, &mut read_fds, std::ptr::null_mut(), std::ptr::null_mut(), tv_ptr);
+
+            if has_input < 0 {
+                // a signal interrupting the wait isnt an error, the caller just checks again
+                if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                    return Ok(false);
+                }
+
+                return Err("failed to wait for stdin");
+            }
+
+            if resize_pipe >= 0 && has_input > 0 && FD_ISSET(resize_pipe, &read_fds) {
+                // empty the pipe so it doesnt wake up the next wait, several resizes become one event
+                let mut buf: [u8; 64] = [0; 64];
+                while read(resize_pipe, buf.as_mut_ptr() as *mut c_void, buf.len()) > 0 {}
 
-            let has_input = select(1, &mut read_fds, std::ptr::null_mut(), std::ptr::null_mut(), &mut tv);
+                self.resized = true;
+            }
         
-            if has_input > 0 {
-                let mut buf: [u8; 1] = [0];
-                let err = read(STDIN_FILENO, buf.as_mut_ptr() as *mut c_void, 1);
+            if has_input > 0 && FD_ISSET(STDIN_FILENO, &read_fds) {
+                // read everything that is available so escape sequences arrive in one piece
+                let mut buf: [u8; 64] = [0; 64];
+                let count = read(STDIN_FILENO, buf.as_mut_ptr() as *mut c_void, buf.len());
 
-                if err == 1 {
-                    result = Ok(Some(buf[0] as char));
+                if count <= 0 {
+                    return Err("failed to read byte from stdin");
+                }
+
+                self.input.extend(&buf[..count as usize]);
+            }   
+        }
+
+        Ok(!self.input.is_empty())
+    }
+}
+
+
+
+// creates the resize pipe and makes sigwinch write to it, resize events just never show up if this fails
+#[cfg(target_family = "unix")]
+fn install_resize_handler() {
+    extern "C" fn handle_sigwinch(signum: c_int, info: *mut siginfo_t, context: *mut c_void) {
+        // only async signal safe calls are allowed in here, and errno has to be left the way the interrupted code had it
+        unsafe {
+            let errno = errno();
+            let saved_errno = errno.map(|errno| *errno);
+            let fd = RESIZE_PIPE_WRITE.load(Ordering::Relaxed);
+
+            if fd >= 0 {
+                // a full pipe already means a resize is pending
+                write(fd, b"w".as_ptr() as *const c_void, 1);
+            }
+
+            let previous = PREVIOUS_RESIZE_HANDLER.load(Ordering::Relaxed);
+
+            if previous != SIG_DFL && previous != SIG_IGN {
+                if PREVIOUS_RESIZE_HANDLER_SIGINFO.load(Ordering::Relaxed) {
+                    let previous: extern "C" fn(c_int, *mut siginfo_t, *mut c_void) = std::mem::transmute(previous);
+                    previous(signum, info, context);
                 }
                 else {
-                    result = Err("failed to read byte from stdin");
+                    let previous: extern "C" fn(c_int) = std::mem::transmute(previous);
+                    previous(signum);
                 }
-            }   
+            }
+
+            if let (Some(errno), Some(saved_errno)) = (errno, saved_errno) {
+                *errno = saved_errno;
+            }
         }
+    }
 
-        if canon {
-            self.disable_raw_mode()?;
+    unsafe {
+        let mut fds: [c_int; 2] = [-1, -1];
+
+        if pipe(fds.as_mut_ptr()) == -1 {
+            return;
         }
 
-        if echo {
-            self.enable_echo()?;
+        for fd in fds {
+            fcntl(fd, F_SETFL, fcntl(fd, F_GETFL) | O_NONBLOCK);
+            fcntl(fd, F_SETFD, FD_CLOEXEC);
         }
 
-        result
+        RESIZE_PIPE_READ.store(fds[0], Ordering::Relaxed);
+        RESIZE_PIPE_WRITE.store(fds[1], Ordering::Relaxed);
+
+        // the previous handler is remembered before ours goes in so a resize cant show up before it is known
+        let mut previous: sigaction = std::mem::zeroed();
+
+        if sigaction(SIGWINCH, std::ptr::null(), &mut previous) == -1 {
+            return;
+        }
+
+        PREVIOUS_RESIZE_HANDLER.store(previous.sa_sigaction, Ordering::Relaxed);
+        PREVIOUS_RESIZE_HANDLER_SIGINFO.store(previous.sa_flags & SA_SIGINFO != 0, Ordering::Relaxed);
+
+        let mut action: sigaction = std::mem::zeroed();
+        action.sa_sigaction = handle_sigwinch as extern "C" fn(c_int, *mut siginfo_t, *mut c_void) as sighandler_t;
+        action.sa_flags = SA_SIGINFO | SA_RESTART;
+        sigemptyset(&mut action.sa_mask);
+
+        sigaction(SIGWINCH, &action, std::ptr::null_mut());
+    }
+}
+
+// where errno of the current thread is, every libc calls the function for it something else,
+// None on the unix targets left out here so the resize handler just doesnt save errno on them
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "emscripten", target_os = "redox"))]
+unsafe fn errno() -> Option<*mut c_int> {
+    Some(libc::__errno_location())
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "freebsd"))]
+unsafe fn errno() -> Option<*mut c_int> {
+    Some(libc::__error())
+}
+
+#[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+unsafe fn errno() -> Option<*mut c_int> {
+    Some(libc::__errno())
+}
+
+#[cfg(any(target_os = "solaris", target_os = "illumos"))]
+unsafe fn errno() -> Option<*mut c_int> {
+    Some(libc::___errno())
+}
+
+#[cfg(target_os = "haiku")]
+unsafe fn errno() -> Option<*mut c_int> {
+    Some(libc::_errnop())
+}
+
+#[cfg(target_os = "aix")]
+unsafe fn errno() -> Option<*mut c_int> {
+    Some(libc::_Errno())
+}
+
+#[cfg(all(target_family = "unix", not(any(
+    target_os = "linux", target_os = "dragonfly", target_os = "emscripten", target_os = "redox",
+    target_os = "macos", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "freebsd",
+    target_os = "android", target_os = "netbsd", target_os = "openbsd",
+    target_os = "solaris", target_os = "illumos", target_os = "haiku", target_os = "aix"
+))))]
+unsafe fn errno() -> Option<*mut c_int> {
+    None
+}
+
+// takes the cursor position report off the end of the bytes if it is there, returning it as 0 based (x, y)
+#[cfg(target_family = "unix")]
+fn cursor_report(bytes: &mut Vec<u8>) -> Option<(i64, i64)> {
+    if bytes.last() != Some(&b'R') {
+        return None;
+    }
+
+    let start = bytes.iter().rposition(|&byte| byte == 0x1b)?;
+    let report = std::str::from_utf8(&bytes[start + 1..bytes.len() - 1]).ok()?;
+    let (row, column) = report.strip_prefix('[')?.split_once(';')?;
+    let position = (column.parse::<i64>().ok()? - 1, row.parse::<i64>().ok()? - 1);
+
+    bytes.truncate(start);
+    Some(position)
+}
+
+// returns the id of a timer that is due at the given time and schedules its next tick
+#[cfg(target_family = "unix")]
+fn fire_timer(timers: &mut Vec<Timer>, now: Instant) -> Option<TimerId> {
+    let index = timers.iter().position(|timer| timer.deadline <= now)?;
+    let id = timers[index].id;
+
+    match timers[index].interval {
+        Some(interval) => {
+            let timer = &mut timers[index];
+            timer.deadline += interval;
+
+            // skip the ticks that were missed instead of sending them all at once
+            if timer.deadline <= now {
+                timer.deadline = now + interval;
+            }
+        },
+        None => {
+            timers.remove(index);
+        }
+    }
+
+    Some(id)
+}
+
+#[cfg(target_family = "unix")]
+fn next_deadline(timers: &[Timer]) -> Option<Instant> {
+    timers.iter().map(|timer| timer.deadline).min()
+}
+
+#[cfg(target_family = "unix")]
+fn write_stdout(bytes: &[u8]) -> Result<(), &'static str> {
+    let mut stdout = std::io::stdout();
+    stdout.write_all(bytes).map_err(|_| "failed to write to stdout")?;
+    stdout.flush().map_err(|_| "failed to write to stdout")
+}
+
+// the bytes after the first one of a sequence are sent together, so they get a short timeout
+#[cfg(target_family = "unix")]
+impl ByteSource for Term {
+    fn next_byte(&mut self) -> Result<Option<u8>, &'static str> {
+        self.read_byte(self.escape_timeout)
+    }
+
+    fn next_byte_within(&mut self, timeout: Duration) -> Result<Option<u8>, &'static str> {
+        self.read_byte(timeout)
+    }
+
+    fn unread(&mut self, byte: u8) {
+        self.input.push_front(byte);
+    }
+}
+
+
+
+/*
+    ##### SHARED PORTION #####
+*/
+
+
+
+impl Term {
+    /// Returns the size of the terminal as (width, height) in cells, if it cant be queried (like when stdout isnt a terminal)
+    /// the COLUMNS and LINES environment variables are used, and if those arent set either 80x24 is used
+    pub fn size_or_default() -> (i64, i64) {
+        if let Ok(size) = Term::size() {
+            return size;
+        }
+
+        let from_env = |name: &str, default: i64| {
+            std::env::var(name).ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .filter(|&value| value > 0)
+                .unwrap_or(default)
+        };
+
+        (from_env("COLUMNS", 80), from_env("LINES", 24))
     }
 }
 
@@ -264,4 +785,160 @@ impl Term {
     pub fn getch(dur: Duration) -> Result<Option<char>, &'static str> {
         Err("getch not implemented yet for windows :p");
     }
-}
\ No newline at end of file
+
+    pub fn read_key(dur: Duration) -> Result<Option<Key>, &'static str> {
+        Err("read_key not implemented yet for windows :p");
+    }
+
+    pub fn read_event(dur: Duration) -> Result<Option<Event>, &'static str> {
+        Err("read_event not implemented yet for windows :p");
+    }
+
+    pub fn wait_event() -> Result<Event, &'static str> {
+        Err("wait_event not implemented yet for windows :p");
+    }
+
+    pub fn size() -> Result<(i64, i64), &'static str> {
+        Err("size not implemented yet for windows :p");
+    }
+
+    pub fn cursor_position() -> Result<(i64, i64), &'static str> {
+        Err("cursor_position not implemented yet for windows :p");
+    }
+}
+
+
+#[cfg(all(test, target_family = "unix"))]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn assert_report(bytes: &[u8], expected: Option<(i64, i64)>, rest: &[u8]) {
+        let mut remaining = bytes.to_vec();
+
+        assert_eq!(cursor_report(&mut remaining), expected, "report in {:?}", bytes.escape_ascii().to_string());
+        assert_eq!(remaining, rest);
+    }
+
+    static PREVIOUS_HANDLER_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    // a handler the host app might have had, it clobbers errno like a careless handler would
+    extern "C" fn previous_handler(_: c_int) {
+        PREVIOUS_HANDLER_CALLS.fetch_add(1, Ordering::SeqCst);
+
+        unsafe {
+            *errno().unwrap() = libc::EBADF;
+        }
+    }
+
+    #[test]
+    fn resize_handler_chains_and_keeps_errno() {
+        unsafe {
+            let mut action: sigaction = std::mem::zeroed();
+            action.sa_sigaction = previous_handler as extern "C" fn(c_int) as sighandler_t;
+            sigemptyset(&mut action.sa_mask);
+            sigaction(SIGWINCH, &action, std::ptr::null_mut());
+
+            RESIZE_HANDLER.call_once(install_resize_handler);
+
+            *errno().unwrap() = libc::EINTR;
+            libc::raise(SIGWINCH);
+
+            assert_eq!(*errno().unwrap(), libc::EINTR);
+            assert_eq!(PREVIOUS_HANDLER_CALLS.load(Ordering::SeqCst), 1);
+
+            let mut buf = [0u8; 8];
+            let count = libc::read(RESIZE_PIPE_READ.load(Ordering::Relaxed), buf.as_mut_ptr() as *mut c_void, buf.len());
+            assert_eq!(count, 1);
+        }
+    }
+
+    #[test]
+    fn cursor_reports() {
+        assert_report(b"\x1b[5;1R", Some((0, 4)), b"");
+        assert_report(b"\x1b[24;80R", Some((79, 23)), b"");
+
+        // keys pressed before the report are left alone
+        assert_report(b"ab\x1b[A\x1b[3;7R", Some((6, 2)), b"ab\x1b[A");
+
+        // not a report yet, or not one at all
+        assert_report(b"\x1b[3;7", None, b"\x1b[3;7");
+        assert_report(b"\x1b[R", None, b"\x1b[R");
+        assert_report(b"R", None, b"R");
+    }
+
+    fn timer(id: u64, deadline: Instant, interval: Option<Duration>) -> Timer {
+        let mut timer = Timer::new(id, Duration::ZERO, interval);
+        timer.deadline = deadline;
+        timer
+    }
+
+    #[test]
+    fn timers_keep_their_schedule() {
+        let start = Instant::now();
+        let interval = Duration::from_millis(10);
+        let mut timers = vec![timer(1, start + interval, Some(interval))];
+        let id = timers[0].id;
+
+        assert_eq!(fire_timer(&mut timers, start), None);
+
+        // going off a little late doesnt push the next tick back
+        assert_eq!(fire_timer(&mut timers, start + Duration::from_millis(12)), Some(id));
+        assert_eq!(timers[0].deadline, start + interval * 2);
+        assert_eq!(fire_timer(&mut timers, start + Duration::from_millis(12)), None);
+    }
+
+    #[test]
+    fn timers_skip_missed_ticks() {
+        let start = Instant::now();
+        let interval = Duration::from_millis(10);
+        let mut timers = vec![timer(1, start, Some(interval))];
+        let id = timers[0].id;
+
+        // three ticks were missed, only one is sent and the next one is a full interval away
+        let now = start + Duration::from_millis(35);
+        assert_eq!(fire_timer(&mut timers, now), Some(id));
+        assert_eq!(fire_timer(&mut timers, now), None);
+        assert_eq!(timers[0].deadline, now + interval);
+    }
+
+    #[test]
+    fn timeouts_go_off_once() {
+        let start = Instant::now();
+        let mut timers = vec![timer(1, start, None), timer(2, start + Duration::from_secs(1), None)];
+        let (first, second) = (timers[0].id, timers[1].id);
+
+        assert_eq!(fire_timer(&mut timers, start), Some(first));
+        assert_eq!(fire_timer(&mut timers, start), None);
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers[0].id, second);
+    }
+
+    #[test]
+    fn due_timers_go_off_one_at_a_time() {
+        let start = Instant::now();
+        let interval = Duration::from_secs(1);
+        let mut timers = vec![timer(1, start, Some(interval)), timer(2, start, None)];
+        let (first, second) = (timers[0].id, timers[1].id);
+
+        assert_eq!(fire_timer(&mut timers, start), Some(first));
+        assert_eq!(fire_timer(&mut timers, start), Some(second));
+        assert_eq!(fire_timer(&mut timers, start), None);
+    }
+
+    #[test]
+    fn next_deadline_is_the_soonest_timer() {
+        let start = Instant::now();
+        assert_eq!(next_deadline(&[]), None);
+
+        let mut timers = vec![
+            timer(1, start + Duration::from_millis(30), Some(Duration::from_millis(30))),
+            timer(2, start + Duration::from_millis(20), None)
+        ];
+        assert_eq!(next_deadline(&timers), Some(start + Duration::from_millis(20)));
+
+        // once the timeout is gone the interval timer is next
+        fire_timer(&mut timers, start + Duration::from_millis(20));
+        assert_eq!(next_deadline(&timers), Some(start + Duration::from_millis(30)));
+    }
+}
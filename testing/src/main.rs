@@ -1,18 +1,15 @@
-use std::time::Duration;
 use nox_lib::graphics::prelude::*;
-use nox_lib::io::Term;
+use nox_lib::io::{Event, KeyCode, MouseButton, MouseKind, Session};
 
 fn main() {
     let width = 40;
     let height = 20;
 
     let mut gui = Gui::new(width, height);
-    let mut term = Term::new().unwrap();
+    let mut session = Session::new().unwrap();
+    session.term_mut().enable_mouse().unwrap();
 
-    term.save_state().unwrap();
-    term.enable_raw_input().unwrap();
-    gui.clear_terminal();
-    gui.hide_cursor();
+    gui.clear_terminal().unwrap();
 
     
 
@@ -24,27 +21,30 @@ fn main() {
 
     let mut running = true;
     while running {
-        match term.getch(Duration::ZERO).unwrap() {
-            None => (),
-            Some(c) => {
-                match c {
-                    'w' => y -= 1,
-                    's' => y += 1,
-                    'a' => x -= 1,
-                    'd' => x += 1,
-                    'q' => running = false,
-                    _ => ()
-                }
-            }
-        };
-
         gui.clear(' ', Color::DEFAULT, Color::new(0, 0, 50));
         gui.line(pos.0, pos.1, pos.0 + x, pos.1 + y, '*', Color::WHITE, Color::DEFAULT);
 
-        gui.display();
+        gui.display().unwrap();
+
+        match session.term_mut().wait_event().unwrap() {
+            Event::Key(key) => match key.code {
+                KeyCode::Up | KeyCode::Char('w') => y -= 1,
+                KeyCode::Down | KeyCode::Char('s') => y += 1,
+                KeyCode::Left | KeyCode::Char('a') => x -= 1,
+                KeyCode::Right | KeyCode::Char('d') => x += 1,
+                KeyCode::Esc | KeyCode::Char('q') => running = false,
+                _ => ()
+            },
+            // clicking or dragging moves the end of the line to the mouse
+            Event::Mouse(mouse) => {
+                if let Some(mouse) = gui.mouse_event(mouse) {
+                    if let MouseKind::Down(MouseButton::Left) | MouseKind::Drag(MouseButton::Left) = mouse.kind {
+                        x = mouse.x - pos.0;
+                        y = mouse.y - pos.1;
+                    }
+                }
+            },
+            _ => ()
+        }
     }
-
-    gui.show_cursor();
-    term.disable_raw_input().unwrap();
-    term.restore_state().unwrap();
-}
\ No newline at end of file
+}